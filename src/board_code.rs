@@ -0,0 +1,157 @@
+use strum::IntoEnumIterator;
+
+use crate::{
+    playfield::{PlayfieldSlots, SlotState, PLAYFIELD_SLOTS},
+    rustomino::RustominoType,
+};
+
+// bump when the layout of the encoded bytes changes
+const VERSION_PREFIX: &str = "B1";
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// encodes the locked blocks and garbage of a playfield into a short string
+/// that can be pasted into chat or a bug report
+///
+/// the version prefix is followed by url safe base64 of a row count and one
+/// nibble per slot from the bottom up, 0 for empty, 1 for garbage and 2 and up
+/// for the block type, empty rows at the top aren't included
+/// the active block and its ghost aren't part of the board
+pub fn encode(slots: &PlayfieldSlots) -> String {
+    let rows = slots
+        .iter()
+        .rposition(|row| row.iter().any(|slot| slot.is_locked()))
+        .map_or(0, |top| top + 1);
+    let nibbles: Vec<u8> = slots[..rows]
+        .iter()
+        .flatten()
+        .map(|slot| slot_nibble(*slot))
+        .collect();
+    let mut bytes = vec![rows as u8];
+    bytes.extend(
+        nibbles
+            .chunks(2)
+            .map(|pair| pair[0] << 4 | pair.get(1).unwrap_or(&0)),
+    );
+    format!("{}{}", VERSION_PREFIX, to_base64(&bytes))
+}
+
+/// decodes a board encoded by `encode`
+/// returns None if the code is from another version or invalid
+pub fn decode(code: &str) -> Option<PlayfieldSlots> {
+    let bytes = from_base64(code.trim().strip_prefix(VERSION_PREFIX)?)?;
+    let (&rows, packed) = bytes.split_first()?;
+    let rows = rows as usize;
+    let slot_count = rows * PLAYFIELD_SLOTS[0];
+    if rows > PLAYFIELD_SLOTS[1] || packed.len() != slot_count.div_ceil(2) {
+        return None;
+    }
+    let mut slots = [[SlotState::Empty; PLAYFIELD_SLOTS[0]]; PLAYFIELD_SLOTS[1]];
+    let nibbles = packed.iter().flat_map(|byte| [byte >> 4, byte & 0xf]);
+    for (i, nibble) in nibbles.take(slot_count).enumerate() {
+        slots[i / PLAYFIELD_SLOTS[0]][i % PLAYFIELD_SLOTS[0]] = nibble_slot(nibble)?;
+    }
+    Some(slots)
+}
+
+fn slot_nibble(slot: SlotState) -> u8 {
+    match slot {
+        SlotState::Garbage => 1,
+        SlotState::Locked(rtype) => {
+            2 + RustominoType::iter().position(|r| r == rtype).unwrap_or(0) as u8
+        }
+        SlotState::Empty | SlotState::Occupied(_) | SlotState::Ghost(_) => 0,
+    }
+}
+
+fn nibble_slot(nibble: u8) -> Option<SlotState> {
+    match nibble {
+        0 => Some(SlotState::Empty),
+        1 => Some(SlotState::Garbage),
+        n => RustominoType::iter()
+            .nth(n as usize - 2)
+            .map(SlotState::Locked),
+    }
+}
+
+// url safe base64 without padding
+fn to_base64(bytes: &[u8]) -> String {
+    let mut encoded = String::new();
+    for chunk in bytes.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (i, byte)| {
+            bits | (*byte as u32) << (16 - i * 8)
+        });
+        for i in 0..=chunk.len() {
+            encoded.push(ALPHABET[(bits >> (18 - i * 6) & 0x3f) as usize] as char);
+        }
+    }
+    encoded
+}
+
+fn from_base64(encoded: &str) -> Option<Vec<u8>> {
+    let values = encoded
+        .bytes()
+        .map(|c| ALPHABET.iter().position(|a| *a == c).map(|v| v as u32))
+        .collect::<Option<Vec<u32>>>()?;
+    let mut bytes = Vec::new();
+    for chunk in values.chunks(4) {
+        // a single leftover character can't hold a whole byte
+        if chunk.len() == 1 {
+            return None;
+        }
+        let bits = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |bits, (i, value)| bits | value << (18 - i * 6));
+        for i in 0..chunk.len() - 1 {
+            bytes.push((bits >> (16 - i * 8)) as u8);
+        }
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let mut slots = [[SlotState::Empty; PLAYFIELD_SLOTS[0]]; PLAYFIELD_SLOTS[1]];
+        for (i, rtype) in RustominoType::iter().enumerate() {
+            slots[i][i] = SlotState::Locked(rtype);
+        }
+        slots[0][9] = SlotState::Garbage;
+        slots[8][3] = SlotState::Garbage;
+        // the active block and ghost aren't encoded
+        slots[12][4] = SlotState::Occupied(RustominoType::T);
+        slots[10][4] = SlotState::Ghost(RustominoType::T);
+
+        let decoded = decode(&encode(&slots)).unwrap();
+        slots[12][4] = SlotState::Empty;
+        slots[10][4] = SlotState::Empty;
+        assert_eq!(decoded, slots);
+    }
+
+    #[test]
+    fn rejects_another_version() {
+        let code = encode(&[[SlotState::Garbage; PLAYFIELD_SLOTS[0]]; PLAYFIELD_SLOTS[1]]);
+        assert!(decode(&code).is_some());
+        assert!(decode(&code.replacen(VERSION_PREFIX, "B0", 1)).is_none());
+        assert!(decode(&code[VERSION_PREFIX.len()..]).is_none());
+    }
+
+    #[test]
+    fn rejects_the_wrong_number_of_slots() {
+        // one row needs five bytes of slots
+        let code = format!("{}{}", VERSION_PREFIX, to_base64(&[1, 0, 0, 0, 0]));
+        assert!(decode(&code).is_none());
+        let code = format!("{}{}", VERSION_PREFIX, to_base64(&[1, 0, 0, 0, 0, 0]));
+        assert!(decode(&code).is_some());
+    }
+
+    #[test]
+    fn rejects_an_invalid_nibble() {
+        // nibbles past the last block type don't stand for a slot
+        let code = format!("{}{}", VERSION_PREFIX, to_base64(&[1, 0x90, 0, 0, 0, 0]));
+        assert!(decode(&code).is_none());
+    }
+}
@@ -1,21 +1,34 @@
-use ggez::input::keyboard::KeyCode;
-use std::{collections::HashMap, time};
+use ggez::input::{gamepad::gilrs::Button, keyboard::KeyCode};
+use std::collections::{HashMap, HashSet};
 use strum::{EnumIter, IntoEnumIterator};
 
+use crate::settings::{KeyLayout, Settings, SoftDropSpeed};
+
 // default control settings
-const LEFT_KEYS: [Option<KeyCode>; 2] = [Some(KeyCode::Left), Some(KeyCode::A)];
-const RIGHT_KEYS: [Option<KeyCode>; 2] = [Some(KeyCode::Right), Some(KeyCode::D)];
-const ROTATE_CW_KEYS: [Option<KeyCode>; 2] = [Some(KeyCode::Up), Some(KeyCode::W)];
-const ROTATE_CCW_KEYS: [Option<KeyCode>; 2] = [Some(KeyCode::LControl), Some(KeyCode::Z)];
-const SOFT_DROP_KEYS: [Option<KeyCode>; 2] = [Some(KeyCode::Down), Some(KeyCode::S)];
-const HARD_DROP_KEYS: [Option<KeyCode>; 2] = [Some(KeyCode::Space), None];
-const HOLD_KEYS: [Option<KeyCode>; 2] = [Some(KeyCode::LShift), Some(KeyCode::C)];
-
-// input repeat delays
-const TRANSLATE_ACTION_DELAY: f64 = 0.3;
-const TRANSLATE_ACTION_REPEAT_DELAY: f64 = 0.025;
-const SOFT_DROP_ACTION_DELAY: f64 = 0.2;
-const SOFT_DROP_ACTION_REPEAT_DELAY: f64 = 0.03;
+const LEFT_KEYS: [Option<KeyCode>; 3] = [Some(KeyCode::Left), Some(KeyCode::A), None];
+const RIGHT_KEYS: [Option<KeyCode>; 3] = [Some(KeyCode::Right), Some(KeyCode::D), None];
+const ROTATE_CW_KEYS: [Option<KeyCode>; 3] = [Some(KeyCode::Up), Some(KeyCode::W), None];
+const ROTATE_CCW_KEYS: [Option<KeyCode>; 3] = [Some(KeyCode::LControl), Some(KeyCode::Z), None];
+const ROTATE_180_KEYS: [Option<KeyCode>; 3] = [Some(KeyCode::X), None, None];
+const SOFT_DROP_KEYS: [Option<KeyCode>; 3] = [Some(KeyCode::Down), Some(KeyCode::S), None];
+const HARD_DROP_KEYS: [Option<KeyCode>; 3] = [Some(KeyCode::Space), None, None];
+const HOLD_KEYS: [Option<KeyCode>; 3] = [
+    Some(KeyCode::LShift),
+    Some(KeyCode::RShift),
+    Some(KeyCode::C),
+];
+const PAUSE_KEYS: [Option<KeyCode>; 3] = [Some(KeyCode::Escape), None, None];
+const PAUSE_BUTTON: Button = Button::Start;
+// the left handed layout mirrors the keys on the left of the keyboard
+// to the right, movement is already on both the arrows and WASD
+const LEFT_HANDED_ROTATE_CCW_KEYS: [Option<KeyCode>; 3] =
+    [Some(KeyCode::RControl), Some(KeyCode::Slash), None];
+const LEFT_HANDED_ROTATE_180_KEYS: [Option<KeyCode>; 3] = [Some(KeyCode::Period), None, None];
+const LEFT_HANDED_HOLD_KEYS: [Option<KeyCode>; 3] = [
+    Some(KeyCode::RShift),
+    Some(KeyCode::LShift),
+    Some(KeyCode::Comma),
+];
 
 // TODO: implement saving and loading inputs from file
 
@@ -23,8 +36,8 @@ const SOFT_DROP_ACTION_REPEAT_DELAY: f64 = 0.03;
 pub enum InputState {
     #[default]
     Up,
-    Down(time::Instant),
-    Held(time::Instant),
+    Down(f64), // time the control has been down, in simulation time
+    Held(f64), // time since the control last repeated, in simulation time
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter)]
@@ -33,93 +46,257 @@ pub enum Control {
     Right,
     RotateCW,
     RotateCCW,
+    Rotate180,
     SoftDrop,
     HardDrop,
     Hold,
+    Pause,
 }
 
 impl Control {
-    pub fn action_delay(&self) -> Option<f64> {
+    pub fn action_delay(&self, settings: &Settings) -> Option<f64> {
         match self {
-            Control::Left | Control::Right => Some(TRANSLATE_ACTION_DELAY),
-            Control::SoftDrop => Some(SOFT_DROP_ACTION_DELAY),
+            Control::Left | Control::Right => Some(settings.das),
+            Control::SoftDrop if settings.soft_drop_speed == SoftDropSpeed::Repeat => {
+                Some(settings.soft_drop_das)
+            }
             _ => None,
         }
     }
-    pub fn action_repeat_delay(&self) -> Option<f64> {
+    pub fn action_repeat_delay(&self, settings: &Settings) -> Option<f64> {
         match self {
-            Control::Left | Control::Right => Some(TRANSLATE_ACTION_REPEAT_DELAY),
-            Control::SoftDrop => Some(SOFT_DROP_ACTION_REPEAT_DELAY),
+            Control::Left | Control::Right => Some(settings.arr),
+            Control::SoftDrop if settings.soft_drop_speed == SoftDropSpeed::Repeat => {
+                Some(settings.soft_drop_arr)
+            }
             _ => None,
         }
     }
-    pub fn default_keys(&self) -> [Option<KeyCode>; 2] {
+    /// controls that act for as long as they're held instead of repeating
+    pub fn continuous(&self, settings: &Settings) -> bool {
+        matches!(self, Control::SoftDrop) && settings.soft_drop_speed != SoftDropSpeed::Repeat
+    }
+    /// every control acts on the active block apart from pause
+    pub fn moves_block(&self) -> bool {
+        !matches!(self, Control::Pause)
+    }
+    pub fn default_keys(&self) -> [Option<KeyCode>; 3] {
         match self {
             Control::Left => LEFT_KEYS,
             Control::Right => RIGHT_KEYS,
             Control::RotateCW => ROTATE_CW_KEYS,
             Control::RotateCCW => ROTATE_CCW_KEYS,
+            Control::Rotate180 => ROTATE_180_KEYS,
             Control::SoftDrop => SOFT_DROP_KEYS,
             Control::HardDrop => HARD_DROP_KEYS,
             Control::Hold => HOLD_KEYS,
+            Control::Pause => PAUSE_KEYS,
+        }
+    }
+    /// the default keys for the provided layout
+    pub fn layout_keys(&self, layout: KeyLayout) -> [Option<KeyCode>; 3] {
+        match (layout, self) {
+            (KeyLayout::LeftHanded, Control::RotateCCW) => LEFT_HANDED_ROTATE_CCW_KEYS,
+            (KeyLayout::LeftHanded, Control::Rotate180) => LEFT_HANDED_ROTATE_180_KEYS,
+            (KeyLayout::LeftHanded, Control::Hold) => LEFT_HANDED_HOLD_KEYS,
+            _ => self.default_keys(),
+        }
+    }
+    pub fn default_button(&self) -> Option<Button> {
+        match self {
+            Control::Pause => Some(PAUSE_BUTTON),
+            _ => None,
         }
     }
 }
 
+/// actions used to navigate the menus
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuAction {
+    Up,
+    Down,
+    Left,
+    Right,
+    Select,
+    Back,
+}
+
+impl MenuAction {
+    pub fn from_key(keycode: KeyCode) -> Option<MenuAction> {
+        match keycode {
+            KeyCode::Up => Some(MenuAction::Up),
+            KeyCode::Down => Some(MenuAction::Down),
+            KeyCode::Left => Some(MenuAction::Left),
+            KeyCode::Right => Some(MenuAction::Right),
+            KeyCode::Return | KeyCode::NumpadEnter => Some(MenuAction::Select),
+            KeyCode::Escape => Some(MenuAction::Back),
+            _ => None,
+        }
+    }
+
+    pub fn from_button(button: Button) -> Option<MenuAction> {
+        match button {
+            Button::DPadUp => Some(MenuAction::Up),
+            Button::DPadDown => Some(MenuAction::Down),
+            Button::DPadLeft => Some(MenuAction::Left),
+            Button::DPadRight => Some(MenuAction::Right),
+            Button::South | Button::Start => Some(MenuAction::Select),
+            Button::East | Button::Select => Some(MenuAction::Back),
+            _ => None,
+        }
+    }
+
+    /// adjusting a setting repeats while the key is held,
+    /// other actions only happen once per press
+    pub fn repeats(&self) -> bool {
+        matches!(self, MenuAction::Left | MenuAction::Right)
+    }
+}
+
 pub struct GameControls {
-    pub input_map: HashMap<Control, [Option<KeyCode>; 2]>,
+    pub input_map: HashMap<Control, [Option<KeyCode>; 3]>,
     pub key_map: HashMap<KeyCode, Control>,
+    pub button_map: HashMap<Button, Control>,
     pub input_states: HashMap<Control, InputState>,
+    pressed_keys: HashSet<KeyCode>, // bound keys that are currently down
 }
 
 impl Default for GameControls {
     fn default() -> Self {
+        Self::with_preset(KeyPreset::default())
+    }
+}
+
+impl GameControls {
+    /// uses the standard bindings if the preset isn't playable
+    pub fn with_preset(preset: KeyPreset) -> Self {
+        let input_map = if playable(&preset) {
+            preset.input_map()
+        } else {
+            KeyPreset::default().input_map()
+        };
         Self {
-            input_map: { Control::iter().map(|i| (i, i.default_keys())).collect() },
-            key_map: {
-                LEFT_KEYS
-                    .iter()
-                    .flatten()
-                    .map(|e| (*e, Control::Left))
-                    .chain(RIGHT_KEYS.iter().flatten().map(|e| (*e, Control::Right)))
-                    .chain(
-                        ROTATE_CW_KEYS
-                            .iter()
-                            .flatten()
-                            .map(|e| (*e, Control::RotateCW)),
-                    )
-                    .chain(
-                        ROTATE_CCW_KEYS
-                            .iter()
-                            .flatten()
-                            .map(|e| (*e, Control::RotateCCW)),
-                    )
-                    .chain(
-                        SOFT_DROP_KEYS
-                            .iter()
-                            .flatten()
-                            .map(|e| (*e, Control::SoftDrop)),
-                    )
-                    .chain(
-                        HARD_DROP_KEYS
-                            .iter()
-                            .flatten()
-                            .map(|e| (*e, Control::HardDrop)),
-                    )
-                    .chain(HOLD_KEYS.iter().flatten().map(|e| (*e, Control::Hold)))
-                    .collect::<HashMap<KeyCode, Control>>()
+            key_map: key_map(&input_map),
+            input_map,
+            button_map: {
+                Control::iter()
+                    .filter_map(|c| c.default_button().map(|b| (b, c)))
+                    .collect::<HashMap<Button, Control>>()
             },
             input_states: {
                 Control::iter()
                     .map(|e| (e, InputState::default()))
                     .collect::<HashMap<Control, InputState>>()
             },
+            pressed_keys: HashSet::new(),
+        }
+    }
+}
+
+/// the choices that generate the key bindings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyPreset {
+    pub layout: KeyLayout,
+    pub swap_drops: bool, // for players used to hard dropping with down
+}
+
+impl Default for KeyPreset {
+    fn default() -> Self {
+        Self {
+            layout: KeyLayout::Standard,
+            swap_drops: false,
         }
     }
 }
 
+impl KeyPreset {
+    pub fn from_settings(settings: &Settings) -> Self {
+        Self {
+            layout: settings.key_layout,
+            swap_drops: settings.swap_drop_keys,
+        }
+    }
+
+    /// every combination of layout and swapped drops
+    pub fn all() -> impl Iterator<Item = KeyPreset> {
+        KeyLayout::iter()
+            .flat_map(|layout| [false, true].map(|swap_drops| KeyPreset { layout, swap_drops }))
+    }
+
+    pub fn keys(&self, control: Control) -> [Option<KeyCode>; 3] {
+        match (self.swap_drops, control) {
+            (true, Control::SoftDrop) => Control::HardDrop.layout_keys(self.layout),
+            (true, Control::HardDrop) => Control::SoftDrop.layout_keys(self.layout),
+            _ => control.layout_keys(self.layout),
+        }
+    }
+
+    pub fn input_map(&self) -> HashMap<Control, [Option<KeyCode>; 3]> {
+        Control::iter().map(|c| (c, self.keys(c))).collect()
+    }
+}
+
+fn key_map(input_map: &HashMap<Control, [Option<KeyCode>; 3]>) -> HashMap<KeyCode, Control> {
+    input_map
+        .iter()
+        .flat_map(|(&c, keys)| keys.iter().flatten().map(move |&k| (k, c)))
+        .collect()
+}
+
+/// logs a warning for any control the preset leaves without a key
+/// and any key it binds to more than one control,
+/// returns false if the bindings couldn't be played with
+pub fn playable(preset: &KeyPreset) -> bool {
+    let mut valid = true;
+    let mut bound: HashMap<KeyCode, Control> = HashMap::new();
+    for control in Control::iter() {
+        let keys = preset.keys(control);
+        if keys.iter().all(Option::is_none) {
+            log::warn!("{:?} leaves {:?} without a key", preset, control);
+            valid = false;
+        }
+        for key in keys.into_iter().flatten() {
+            if let Some(other) = bound.insert(key, control) {
+                log::warn!(
+                    "{:?} binds {:?} to both {:?} and {:?}",
+                    preset,
+                    key,
+                    other,
+                    control
+                );
+                valid = false;
+            }
+        }
+    }
+    valid
+}
+
+/// checks every preset the options can choose,
+/// returns false if any of them aren't playable
+pub fn validate_default_keys() -> bool {
+    // check them all so every problem is logged
+    let mut valid = true;
+    for preset in KeyPreset::all() {
+        valid &= playable(&preset);
+    }
+    valid
+}
+
 impl GameControls {
+    /// swap the key bindings for the ones generated by another preset
+    /// returns false and keeps the current bindings if the preset isn't playable
+    pub fn set_preset(&mut self, preset: KeyPreset) -> bool {
+        if !playable(&preset) {
+            return false;
+        }
+        self.input_map = preset.input_map();
+        self.key_map = key_map(&self.input_map);
+        self.clear_inputs();
+        true
+    }
+
     pub fn clear_inputs(&mut self) {
+        self.pressed_keys.clear();
         for input in Control::iter() {
             self.input_states
                 .entry(input)
@@ -127,23 +304,69 @@ impl GameControls {
         }
     }
 
-    pub fn set_pressed(&mut self, keycode: Option<KeyCode>) {
-        for (key, input) in self.key_map.iter() {
-            if keycode == Some(*key) {
-                self.input_states
-                    .entry(*input)
-                    .and_modify(|e| *e = InputState::Down(time::Instant::now()));
+    pub fn is_down(&self, control: Control) -> bool {
+        self.input_states[&control] != InputState::Up
+    }
+
+    /// makes any controls being held wait out their action delay again
+    pub fn reset_charge(&mut self) {
+        for state in self.input_states.values_mut() {
+            if *state != InputState::Up {
+                *state = InputState::Down(0.0);
             }
         }
     }
 
+    pub fn set_pressed(&mut self, keycode: Option<KeyCode>) {
+        let Some(key) = keycode else {
+            return;
+        };
+        let Some(&control) = self.key_map.get(&key) else {
+            return;
+        };
+        self.pressed_keys.insert(key);
+        self.input_states
+            .entry(control)
+            .and_modify(|e| *e = InputState::Down(0.0));
+    }
+
+    /// the control is only released once none of its keys are still down
     pub fn set_released(&mut self, keycode: Option<KeyCode>) {
-        for (key, input) in self.key_map.iter() {
-            if keycode == Some(*key) {
-                self.input_states
-                    .entry(*input)
-                    .and_modify(|e| *e = InputState::Up);
-            }
+        let Some(key) = keycode else {
+            return;
+        };
+        let Some(&control) = self.key_map.get(&key) else {
+            return;
+        };
+        self.pressed_keys.remove(&key);
+        let still_down = self.input_map[&control]
+            .into_iter()
+            .flatten()
+            .any(|k| self.pressed_keys.contains(&k));
+        if !still_down {
+            self.input_states
+                .entry(control)
+                .and_modify(|e| *e = InputState::Up);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn control_stays_down_until_all_its_keys_are_released() {
+        let mut controls = GameControls::default();
+        controls.set_pressed(Some(KeyCode::Left));
+        controls.set_pressed(Some(KeyCode::A));
+        assert!(controls.is_down(Control::Left));
+
+        controls.set_released(Some(KeyCode::Left));
+        assert!(controls.is_down(Control::Left));
+
+        controls.set_released(Some(KeyCode::A));
+        assert!(!controls.is_down(Control::Left));
+        assert_eq!(controls.input_states[&Control::Left], InputState::Up);
+    }
+}
@@ -0,0 +1,123 @@
+use std::collections::VecDeque;
+
+use crate::{
+    controls::Control,
+    playfield::{self, Placement, Playfield, SlotState},
+    rustomino::RustominoType,
+};
+
+// heuristic weights used to score a placement
+const AGGREGATE_HEIGHT_WEIGHT: f64 = -0.51;
+const COMPLETED_LINES_WEIGHT: f64 = 0.76;
+const HOLES_WEIGHT: f64 = -0.36;
+const BUMPINESS_WEIGHT: f64 = -0.18;
+
+const DEMO_ACTION_DELAY: f64 = 0.1; // time between the demo player's inputs
+
+/// plays the game automatically for the menu demo
+pub struct DemoPlayer {
+    plan: VecDeque<Control>, // controls waiting to be issued for the active block
+    time: f64,               // time since the last control was issued
+}
+
+impl DemoPlayer {
+    pub fn new() -> Self {
+        DemoPlayer {
+            plan: VecDeque::new(),
+            time: 0.0,
+        }
+    }
+
+    /// returns the next control the demo player wants to issue, if any
+    /// the next block is looked ahead to when choosing where the active block goes
+    pub fn update(
+        &mut self,
+        delta_time: f64,
+        playfield: &Playfield,
+        next: Option<RustominoType>,
+    ) -> Option<Control> {
+        self.time += delta_time;
+        if self.time < DEMO_ACTION_DELAY {
+            return None;
+        }
+        // plan a route for a new active block
+        if self.plan.is_empty() {
+            self.plan = plan_controls(playfield, next);
+        }
+        let control = self.plan.pop_front()?;
+        self.time = 0.0;
+        log::debug!("demo player issuing control: {:?}", control);
+        Some(control)
+    }
+}
+
+// picks the best placement for the active block and returns the controls to reach it
+fn plan_controls(playfield: &Playfield, next: Option<RustominoType>) -> VecDeque<Control> {
+    let Some(best) = playfield
+        .active_placements()
+        .into_iter()
+        .map(|placement| (look_ahead(&placement, next), placement))
+        .max_by(|(a, _), (b, _)| a.total_cmp(b))
+        .map(|(_, placement)| placement)
+    else {
+        return VecDeque::new();
+    };
+    log::debug!(
+        "demo player chose rotation: {} column: {}",
+        best.rotation,
+        best.column
+    );
+
+    let translate = if best.column < 0 {
+        Control::Left
+    } else {
+        Control::Right
+    };
+    std::iter::repeat_n(Control::RotateCW, best.rotation)
+        .chain(std::iter::repeat_n(
+            translate,
+            best.column.unsigned_abs() as usize,
+        ))
+        .chain(std::iter::once(Control::HardDrop))
+        .collect()
+}
+
+// scores a placement along with the best placement of the next block after it
+// a placement that leaves nowhere for the next block scores the lowest
+fn look_ahead(placement: &Placement, next: Option<RustominoType>) -> f64 {
+    let Some(next) = next else {
+        return evaluate(placement);
+    };
+    let best_next = placement
+        .playfield()
+        .placements(next)
+        .iter()
+        .map(evaluate)
+        .fold(f64::NEG_INFINITY, f64::max);
+    evaluate(placement) + best_next
+}
+
+// scores the playfield left behind by a placement, higher is better
+fn evaluate(placement: &Placement) -> f64 {
+    let heights = playfield::column_heights(&placement.slots);
+
+    let aggregate_height = heights.iter().sum::<usize>();
+    let holes = heights
+        .iter()
+        .enumerate()
+        .map(|(x, height)| {
+            (0..*height)
+                .filter(|y| placement.slots[*y][x] == SlotState::Empty)
+                .count()
+        })
+        .sum::<usize>();
+    let bumpiness = heights
+        .windows(2)
+        .map(|pair| pair[0].abs_diff(pair[1]))
+        .sum::<usize>();
+
+    AGGREGATE_HEIGHT_WEIGHT * aggregate_height as f64
+        + COMPLETED_LINES_WEIGHT * placement.lines_cleared as f64
+        + HOLES_WEIGHT * holes as f64
+        + BUMPINESS_WEIGHT * bumpiness as f64
+}
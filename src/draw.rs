@@ -1,27 +1,108 @@
+use std::collections::HashMap;
+
 use ggez::glam::Vec2;
-use ggez::graphics::{self, Canvas, Color, DrawMode, Rect, StrokeOptions};
+use ggez::graphics::{self, Canvas, Color, DrawMode, Image, Rect, StrokeOptions};
+use ggez::input::keyboard::KeyCode;
 use ggez::{Context, GameResult};
+use strum::IntoEnumIterator;
 
+use crate::controls::Control;
 use crate::menus::{self, Menu};
 use crate::playfield::{self, Playfield, SlotState};
-use crate::rustomino::Rustomino;
+use crate::popups::Popup;
+use crate::records::Records;
+use crate::rustomino::{Rustomino, RustominoType};
+use crate::theme::Theme;
 use crate::util;
 
 const BLOCK_SIZE: f32 = 30.;
-const BLOCK_PADDING: f32 = 1.;
 const STAGING_PADDING: f32 = 2.;
 
-pub const BACKGROUND_COLOR: Color = Color::new(0.0, 0.29, 0.38, 1.0);
 pub const VIEW_WIDTH: f32 = 1024.0;
 pub const VIEW_HEIGHT: f32 = 768.;
 const UI_FONT_SIZE: f32 = 24.0;
-pub const STAGING_BACKGROUND_COLOR: Color = Color::new(0.0, 0.0, 0.0, 0.5);
-const PLAYFIELD_BACKGROUND_COLOR: Color = Color::new(0.0, 0.0, 0.0, 0.5);
-const PREVIEW_BACKGROUND_COLOR: Color = Color::new(0.0, 0.0, 0.0, 0.5);
-const HOLD_BACKGROUND_COLOR: Color = Color::new(0.0, 0.0, 0.0, 0.2);
+const POPUP_FONT_SIZE: f32 = 32.0;
+const COUNTDOWN_FONT_SIZE: f32 = 50.0;
+const DROP_HINT_FONT_SIZE: f32 = 14.0;
+const LEVEL_PROGRESS_FONT_SIZE: f32 = 16.0;
+const HUD_MARGIN: f32 = 10.0; // closest the hud text gets to the edge of the window
+const DROUGHT_FONT_SIZE: f32 = 14.0;
+const HOLDS_REMAINING_FONT_SIZE: f32 = 14.0;
+const LOCK_TIMER_BAR_WIDTH: f32 = 80.0;
+const MENU_BOTTOM_MARGIN: f32 = 20.0; // space left below the last menu item
+const OPTIONS_VALUE_COLOR: Color = Color::new(0.8, 0.8, 0.5, 1.0);
+const SCROLL_INDICATOR_SIZE: f32 = 8.0;
 const OPTIONS_BACKGROUND_COLOR: Color = Color::new(0.34, 0.09, 0.12, 1.);
-const GHOST_COLOR: Color = Color::new(0.7, 0.7, 0.7, 1.0);
+const GARBAGE_COLOR: Color = Color::new(0.45, 0.45, 0.45, 1.0);
+const DROP_HINT_SHADOW_COLOR: Color = Color::new(0.0, 0.0, 0.0, 0.8);
+const HEATMAP_COLOR: Color = Color::new(1.0, 0.3, 0.0, 0.7); // the most used slot, others fade out
 const PAUSED_OVERLAY_COLOR: Color = Color::new(0.1, 0.1, 0.1, 0.6);
+const GAME_OVER_BORDER_COLOR: Color = Color::new(1.0, 0.1, 0.1, 1.0);
+const PLAYFIELD_BORDER_WIDTH: f32 = 4.0;
+const FRAME_MARGIN: f32 = 5.0; // space between the playfield and its frame
+const GAME_OVER_FLASH_TIME: f64 = 1.2; // how long the game over border is shown
+const GAME_OVER_FLASH_INTERVAL: f64 = 0.15; // time between game over border flashes
+const BOX_ANIMATION_TIME: f64 = 0.15; // how long a block takes to settle into the hold/preview box
+const BOX_ANIMATION_START_SCALE: f32 = 0.6;
+const BOX_ANIMATION_SLIDE: f32 = 20.0; // pixels a block slides down into the box
+const SPAWN_FLASH_TIME: f64 = 0.2; // how long a newly spawned block is brightened
+const SPAWN_FLASH_BRIGHTNESS: f32 = 0.6;
+const COLORED_GHOST_ALPHA: f32 = 0.6;
+const MONO_LOCKED_COLOR: Color = Color::new(0.95, 0.95, 0.95, 1.0);
+const MONO_ACTIVE_COLOR: Color = Color::new(1.0, 0.8, 0.0, 1.0);
+const MONO_BORDER_COLOR: Color = Color::new(0.0, 0.0, 0.0, 1.0);
+const MONO_BORDER_WIDTH: f32 = 3.0;
+const SPAWN_GUIDE_HEIGHT: f32 = 4.0;
+const SPAWN_GUIDE_ALPHA: f32 = 0.4;
+const DANGER_TINT_COLOR: Color = Color::new(1.0, 0.0, 0.0, 0.15);
+const DANGER_BORDER_COLOR: Color = Color::new(1.0, 0.5, 0.0, 1.0);
+const METRONOME_COLOR: Color = Color::new(1.0, 1.0, 1.0, 0.6);
+const RUN_INFO_FONT_SIZE: f32 = 12.0;
+const GAME_OVER_MENU_FONT_SIZE: f32 = 20.0;
+const GAME_OVER_MENU_TOP: f32 = 110.0; // below the middle of the view, under the run info
+const RUN_INFO_COLOR: Color = Color::new(0.8, 0.8, 0.8, 1.0);
+const HIGH_SCORE_FONT_SIZE: f32 = 30.0;
+const HIGH_SCORE_COLOR: Color = Color::new(1.0, 0.84, 0.0, 1.0);
+const CONTROLS_HELP_COLOR: Color = Color::new(0.0, 0.0, 0.0, 0.75);
+const CONTROLS_HELP_FONT_SIZE: f32 = 14.0;
+const CONTROLS_HELP_PADDING: f32 = 12.0;
+const KEY_MAP_PREVIEW_FONT_SIZE: f32 = 10.0;
+const MOVE_HISTORY_FONT_SIZE: f32 = 10.0;
+const ACTIVE_POSITION_FONT_SIZE: f32 = 12.0;
+
+/// time since a block entered the hold and preview boxes
+/// and since the active block spawned
+#[derive(Debug, Clone, Copy)]
+pub struct Animations {
+    pub hold: f64,
+    pub next: f64,
+    pub spawn: f64,
+}
+
+impl Animations {
+    /// animations that have already completed
+    pub fn finished() -> Self {
+        Self {
+            hold: BOX_ANIMATION_TIME,
+            next: BOX_ANIMATION_TIME,
+            spawn: SPAWN_FLASH_TIME,
+        }
+    }
+
+    pub fn update(&mut self, delta_time: f64) {
+        self.hold += delta_time;
+        self.next += delta_time;
+        self.spawn += delta_time;
+    }
+}
+
+/// the lockdown progress of the active block shown on the hud
+#[derive(Debug, Clone, Copy)]
+pub struct LockTimer {
+    pub remaining: f32, // fraction of the lockdown delay left
+    pub resets_used: u32,
+    pub max_resets: u32,
+}
 
 #[derive(Debug)]
 pub struct ViewSettings {
@@ -34,34 +115,74 @@ pub struct ViewSettings {
     pub level_label_pos: Vec2,
     pub title_pos: Vec2,
     pub level_pos: Vec2,
+    pub level_progress_pos: Vec2,
+    pub lock_timer_pos: Vec2,
+    pub hud_scale: f32,      // size of the hud text compared to the default
+    pub colored_ghost: bool, // draw the ghost in the active block's color
+    pub mono_blocks: bool,   // draw the stack and active block in single high contrast colors
+    pub show_frame: bool,    // draw a frame around the playfield and staging area
+    pub hide_staging: bool,  // the staging area isn't drawn
+    pub drawn_rows: usize,   // rows of the playfield that blocks are drawn in
     pub score_pos: Vec2,
+    pub theme: Theme,
 }
 
 impl ViewSettings {
-    pub fn new(drawable_width: f32, drawable_height: f32) -> Self {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        drawable_width: f32,
+        drawable_height: f32,
+        show_buffer_rows: bool,
+        hide_staging: bool,
+        theme: Theme,
+        hud_scale: f32,
+        colored_ghost: bool,
+        mono_blocks: bool,
+        show_frame: bool,
+    ) -> Self {
+        let block_padding = theme.block_padding;
+        // hiding the staging area hides the buffer rows with it
+        let show_buffer_rows = show_buffer_rows && !hide_staging;
+        // the two buffer rows at the top of the playfield are normally hidden
+        let visible_rows = if show_buffer_rows {
+            playfield::PLAYFIELD_SLOTS[1]
+        } else {
+            playfield::PLAYFIELD_SLOTS[1] - 2
+        };
         // calculate the playfield dimensions based on block size, padding and playfield slots
         let playfield_w =
-            (playfield::PLAYFIELD_SLOTS[0] as f32 * (BLOCK_SIZE + BLOCK_PADDING)) + BLOCK_PADDING;
-        let playfield_h = ((playfield::PLAYFIELD_SLOTS[1] - 2) as f32
-            * (BLOCK_SIZE + BLOCK_PADDING))
-            + BLOCK_PADDING;
+            (playfield::PLAYFIELD_SLOTS[0] as f32 * (BLOCK_SIZE + block_padding)) + block_padding;
+        let playfield_h = (visible_rows as f32 * (BLOCK_SIZE + block_padding)) + block_padding;
 
         // calculate the dimentions of the staging area
         let staging_w = playfield_w;
-        let staging_h = (2. * (BLOCK_SIZE + BLOCK_PADDING)) + BLOCK_PADDING;
+        let staging_h = (2. * (BLOCK_SIZE + block_padding)) + block_padding;
         // calculate the dimensions of the preview area
-        let preview_w = (4. * (BLOCK_SIZE + BLOCK_PADDING)) + BLOCK_PADDING;
+        let preview_w = (4. * (BLOCK_SIZE + block_padding)) + block_padding;
         let preview_h = staging_h;
         // calculate the dimensions of the hold area
         let hold_w = preview_w;
         let hold_h = staging_h;
+        // a hidden staging area is flattened onto the top edge of the playfield
+        let staging_h = if hide_staging { 0.0 } else { staging_h };
 
         // center playfield
-        let playfield_x = drawable_width / 2.0 - playfield_w / 2.0;
-        let playfield_y = drawable_height / 2.0 - playfield_h / 2.0 + staging_h / 2.0 + 1.0;
+        // rounded to whole pixels so the blocks inside it tile without seams
+        let playfield_x = (drawable_width / 2.0 - playfield_w / 2.0).round();
+        let playfield_y = if show_buffer_rows || hide_staging {
+            drawable_height / 2.0 - playfield_h / 2.0
+        } else {
+            drawable_height / 2.0 - playfield_h / 2.0 + staging_h / 2.0 + 1.0
+        }
+        .round();
         // center staging area above playfield
+        // or over the buffer rows when they are visible
         let staging_x = playfield_x;
-        let staging_y = playfield_y - staging_h - STAGING_PADDING;
+        let staging_y = if show_buffer_rows || hide_staging {
+            playfield_y
+        } else {
+            playfield_y - staging_h - STAGING_PADDING
+        };
         // center preview area to the right of playfield
         let preview_x = playfield_x + playfield_w + 10.0;
         let preview_y = playfield_y;
@@ -69,47 +190,113 @@ impl ViewSettings {
         let hold_x = playfield_x - preview_w - 10.0;
         let hold_y = playfield_y;
 
+        // the hud text is placed beside the hold and preview boxes and spaced out
+        // as it's scaled up so the labels don't overlap, then kept inside the window
+        let view_rect = Rect::new(0., 0., drawable_width, drawable_height);
+        let hud_text = UI_FONT_SIZE * hud_scale;
+        let hud_pos = |x: f32, y: f32, chars: f32| {
+            clamp_to_view(
+                Vec2::new(x, y),
+                Vec2::new(chars * hud_text, hud_text),
+                &view_rect,
+            )
+        };
+        let left_x = hold_x + hold_w + 10.0; // the left edge of the playfield
+        let right_x = preview_x; // just right of the playfield
+        let bottom_y = playfield_y + playfield_h;
+
         Self {
-            view_rect: Rect::new(0., 0., drawable_width, drawable_height),
+            view_rect,
             playfield_rect: Rect::new(playfield_x, playfield_y, playfield_w, playfield_h),
             staging_rect: Rect::new(staging_x, staging_y, staging_w, staging_h),
             preview_rect: Rect::new(preview_x, preview_y, preview_w, preview_h),
             hold_rect: Rect::new(hold_x, hold_y, hold_w, hold_h),
-            score_label_pos: Vec2::new(
-                playfield_x + playfield_w + 30.0,
-                playfield_y + playfield_h - 30.0,
+            score_label_pos: hud_pos(right_x + 20.0, bottom_y - 30.0 * hud_scale, 6.0),
+            level_label_pos: hud_pos(left_x - 180.0 * hud_scale, bottom_y - 30.0 * hud_scale, 6.0),
+            title_pos: hud_pos(hold_x - 140.0, playfield_y - 50.0 * hud_scale, 7.0),
+            level_pos: hud_pos(left_x - 60.0 * hud_scale, bottom_y - 30.0 * hud_scale, 2.0),
+            level_progress_pos: hud_pos(
+                left_x - 180.0 * hud_scale,
+                bottom_y - 60.0 * hud_scale,
+                5.0,
             ),
-            level_label_pos: Vec2::new(playfield_x - 180.0, playfield_y + playfield_h - 30.0),
-            title_pos: Vec2::new(playfield_x - 280.0, playfield_y - 50.0),
-            level_pos: Vec2::new(playfield_x - 60.0, playfield_y + playfield_h - 30.0),
-            score_pos: Vec2::new(
-                playfield_x + playfield_w + 150.0,
-                playfield_y + playfield_h - 30.0,
+            lock_timer_pos: hud_pos(right_x + 20.0, bottom_y - 60.0 * hud_scale, 8.0),
+            score_pos: hud_pos(
+                right_x + 20.0 + 120.0 * hud_scale,
+                bottom_y - 30.0 * hud_scale,
+                7.0,
             ),
+            hud_scale,
+            colored_ghost,
+            mono_blocks,
+            show_frame,
+            hide_staging,
+            drawn_rows: if hide_staging {
+                playfield::PLAYFIELD_SIZE[1] as usize
+            } else {
+                playfield::PLAYFIELD_SLOTS[1]
+            },
+            theme,
         }
     }
 }
 
+/// scales the background image to cover the view, keeping its aspect ratio
+/// and centering it so any overflow is cropped evenly from both sides
+pub fn draw_background_image(canvas: &mut Canvas, image: &Image, view_settings: &ViewSettings) {
+    let view_rect = &view_settings.view_rect;
+    let scale = (view_rect.w / image.width() as f32).max(view_rect.h / image.height() as f32);
+    let (w, h) = (image.width() as f32 * scale, image.height() as f32 * scale);
+    canvas.draw(
+        image,
+        graphics::DrawParam::default()
+            .dest([(view_rect.w - w) / 2.0, (view_rect.h - h) / 2.0])
+            .scale([scale, scale]),
+    );
+}
+
 pub fn draw_playing_backgound(
     ctx: &mut Context,
     canvas: &mut Canvas,
     view_settings: &ViewSettings,
 ) -> GameResult {
+    // frame the playfield and staging area so they read as a single well
+    if view_settings.show_frame {
+        let well_rect = view_settings
+            .playfield_rect
+            .combine_with(view_settings.staging_rect);
+        let frame_rect = Rect::new(
+            well_rect.x - FRAME_MARGIN,
+            well_rect.y - FRAME_MARGIN,
+            well_rect.w + FRAME_MARGIN * 2.0,
+            well_rect.h + FRAME_MARGIN * 2.0,
+        );
+        let frame = graphics::Mesh::new_rectangle(
+            ctx,
+            DrawMode::stroke(view_settings.theme.frame_width),
+            frame_rect,
+            view_settings.theme.frame_color,
+        )?;
+        canvas.draw(&frame, graphics::DrawParam::default());
+    }
+
     // draw the staging background
-    let staging_rect = graphics::Mesh::new_rectangle(
-        ctx,
-        DrawMode::fill(),
-        view_settings.staging_rect,
-        STAGING_BACKGROUND_COLOR,
-    )?;
-    canvas.draw(&staging_rect, graphics::DrawParam::default());
+    if !view_settings.hide_staging {
+        let staging_rect = graphics::Mesh::new_rectangle(
+            ctx,
+            DrawMode::fill(),
+            view_settings.staging_rect,
+            view_settings.theme.staging_background_color,
+        )?;
+        canvas.draw(&staging_rect, graphics::DrawParam::default());
+    }
 
     // draw the playfield background
     let playfield_rect = graphics::Mesh::new_rectangle(
         ctx,
         DrawMode::fill(),
         view_settings.playfield_rect,
-        PLAYFIELD_BACKGROUND_COLOR,
+        view_settings.theme.playfield_background_color,
     )?;
     canvas.draw(&playfield_rect, graphics::DrawParam::default());
 
@@ -118,7 +305,7 @@ pub fn draw_playing_backgound(
         ctx,
         DrawMode::fill(),
         view_settings.preview_rect,
-        PREVIEW_BACKGROUND_COLOR,
+        view_settings.theme.preview_background_color,
     )?;
     canvas.draw(&preview_rect, graphics::DrawParam::default());
 
@@ -127,21 +314,39 @@ pub fn draw_playing_backgound(
         ctx,
         DrawMode::fill(),
         view_settings.hold_rect,
-        HOLD_BACKGROUND_COLOR,
+        view_settings.theme.hold_background_color,
     )?;
     canvas.draw(&hold_rect, graphics::DrawParam::default());
 
     Ok(())
 }
 
+// the color of a block in the playfield
+// mono blocks ignore the block type and only separate the stack from the active block
+fn block_color(rtype: RustominoType, slot: &SlotState, view_settings: &ViewSettings) -> Color {
+    if !view_settings.mono_blocks {
+        return rtype.color();
+    }
+    if matches!(slot, SlotState::Occupied(_)) {
+        MONO_ACTIVE_COLOR
+    } else {
+        MONO_LOCKED_COLOR
+    }
+}
+
 fn draw_playfield(
     ctx: &mut Context,
     canvas: &mut Canvas,
     playfield: &Playfield,
-    staging_rect: &Rect,
-    playfield_rect: &Rect,
+    view_settings: &ViewSettings,
+    spawn_time: f64,
+    danger: bool,
     game_over: bool,
 ) -> GameResult {
+    let theme = &view_settings.theme;
+    // flash the active block brighter just after it spawns
+    let spawn_flash = (1.0 - (spawn_time / SPAWN_FLASH_TIME).clamp(0.0, 1.0)) as f32;
+
     // create a mesh we'll reuse for each block
     let block_mesh = graphics::Mesh::new_rectangle(
         ctx,
@@ -150,39 +355,126 @@ fn draw_playfield(
         Color::new(1.0, 1.0, 1.0, 1.0),
     )?;
 
+    // warn the player when the stack is getting close to the top
+    if danger && !game_over {
+        let danger_tint = graphics::Mesh::new_rectangle(
+            ctx,
+            DrawMode::fill(),
+            view_settings.playfield_rect,
+            DANGER_TINT_COLOR,
+        )?;
+        canvas.draw(&danger_tint, graphics::DrawParam::default());
+        draw_playfield_border(ctx, canvas, view_settings, DANGER_BORDER_COLOR)?;
+    }
+
     // draw the playfield
     let draw_param = graphics::DrawParam::default();
-    for (y, slots_x) in playfield.slots.iter().enumerate() {
+
+    // a shadow under the active block helps it stand out from the background
+    if theme.active_shadow_offset > 0.0 && !game_over {
+        for (y, slots_x) in visible_slots(playfield, view_settings) {
+            for (x, slot) in slots_x.iter().enumerate() {
+                if matches!(slot, SlotState::Occupied(_)) {
+                    let mut rect = playfield_block_rect([x as i32, y as i32], view_settings);
+                    rect.translate([theme.active_shadow_offset, theme.active_shadow_offset]);
+                    canvas.draw(
+                        &block_mesh,
+                        draw_param.dest_rect(rect).color(theme.active_shadow_color),
+                    );
+                }
+            }
+        }
+    }
+    for (y, slots_x) in visible_slots(playfield, view_settings) {
         for (x, slot) in slots_x.iter().enumerate() {
             match slot {
+                SlotState::Occupied(rtype) if !game_over && spawn_flash > 0.0 => {
+                    let rect = playfield_block_rect([x as i32, y as i32], view_settings);
+                    let color = util::brighten(
+                        block_color(*rtype, slot, view_settings),
+                        spawn_flash * SPAWN_FLASH_BRIGHTNESS,
+                    );
+                    canvas.draw(&block_mesh, draw_param.dest_rect(rect).color(color));
+                }
                 SlotState::Locked(rtype) | SlotState::Occupied(rtype) => {
                     // draw the block
-                    let rect =
-                        playfield_block_rect([x as i32, y as i32], staging_rect, playfield_rect);
+                    let rect = playfield_block_rect([x as i32, y as i32], view_settings);
                     let color = if game_over {
-                        util::rgb_to_grayscale(rtype.color())
+                        util::rgb_to_grayscale(block_color(*rtype, slot, view_settings))
+                    } else if matches!(slot, SlotState::Locked(_)) && !view_settings.mono_blocks {
+                        // shade the stack so it stands apart from the active block
+                        util::darken(rtype.color(), theme.locked_block_shade)
                     } else {
-                        rtype.color()
+                        block_color(*rtype, slot, view_settings)
                     };
                     canvas.draw(&block_mesh, draw_param.dest_rect(rect).color(color));
                 }
+                SlotState::Garbage => {
+                    let rect = playfield_block_rect([x as i32, y as i32], view_settings);
+                    canvas.draw(&block_mesh, draw_param.dest_rect(rect).color(GARBAGE_COLOR));
+                }
                 _ => {}
             }
         }
     }
 
+    // outline the blocks when the theme has a border
+    // mono blocks are always outlined so neighbouring blocks can be told apart
+    let (border_width, border_color) = if view_settings.mono_blocks {
+        (MONO_BORDER_WIDTH, MONO_BORDER_COLOR)
+    } else {
+        (theme.block_border_width, theme.block_border_color)
+    };
+    if border_width > 0.0 {
+        let border_mesh = graphics::Mesh::new_rectangle(
+            ctx,
+            DrawMode::Stroke(StrokeOptions::default().with_line_width(border_width / BLOCK_SIZE)),
+            Rect::new(0.0, 0.0, 1.0, 1.0),
+            border_color,
+        )?;
+        for (y, slots_x) in visible_slots(playfield, view_settings) {
+            for (x, slot) in slots_x.iter().enumerate() {
+                if slot.is_locked() || matches!(slot, SlotState::Occupied(_)) {
+                    let rect = playfield_block_rect([x as i32, y as i32], view_settings);
+                    canvas.draw(&border_mesh, draw_param.dest_rect(rect));
+                }
+            }
+        }
+    }
+
     let ghost_mesh = graphics::Mesh::new_rectangle(
         ctx,
-        DrawMode::Stroke(StrokeOptions::default().with_line_width(0.1)),
+        DrawMode::Stroke(StrokeOptions::default().with_line_width(theme.ghost_line_width)),
         Rect::new(0.0, 0.0, 1.0, 1.0),
-        GHOST_COLOR,
+        Color::new(1.0, 1.0, 1.0, 1.0),
     )?;
 
     if let Some(ghost) = &playfield.ghost_rustomino {
+        // match the ghost to the block it belongs to so it's easy to tell apart
+        let ghost_color = if view_settings.colored_ghost {
+            let mut color = block_color(
+                ghost.rtype,
+                &SlotState::Occupied(ghost.rtype),
+                view_settings,
+            );
+            color.a = COLORED_GHOST_ALPHA;
+            color
+        } else {
+            theme.ghost_color
+        };
         for block in ghost.playfield_slots() {
+            // the active block is always drawn over its ghost
+            if block[1] as usize >= view_settings.drawn_rows
+                || matches!(
+                    playfield.slots[block[1] as usize][block[0] as usize],
+                    SlotState::Occupied(_)
+                )
+            {
+                continue;
+            }
             // draw the block
-            let rect = playfield_block_rect([block[0], block[1]], staging_rect, playfield_rect);
-            canvas.draw(&ghost_mesh, draw_param.dest_rect(rect));
+            let rect = playfield_block_rect([block[0], block[1]], view_settings);
+            canvas.draw(&ghost_mesh, draw_param.dest_rect(rect).color(ghost_color));
         }
     }
 
@@ -193,7 +485,9 @@ fn draw_hold(
     ctx: &mut Context,
     canvas: &mut Canvas,
     hold_rustomino: &Option<Rustomino>,
-    hold_rect: &Rect,
+    view_settings: &ViewSettings,
+    hold_available: bool,
+    animation_time: f64,
     game_over: bool,
 ) -> GameResult {
     // create a mesh we'll reuse for each block
@@ -205,13 +499,19 @@ fn draw_hold(
     )?;
 
     let draw_param = graphics::DrawParam::new();
-    if let Some(next) = hold_rustomino {
-        for block in next.blocks {
-            let rect = hold_block_rect([block[0], block[1]], hold_rect);
-            let color = if game_over {
-                util::rgb_to_grayscale(next.rtype.color())
+    if let Some(held) = hold_rustomino {
+        // always show the spawn orientation, however the held block was stored
+        for block in Rustomino::new(held.rtype).blocks {
+            let rect = animated_block_rect(
+                hold_block_rect([block[0], block[1]], view_settings),
+                &view_settings.hold_rect,
+                animation_time,
+            );
+            // dim the held block while hold can't be used
+            let color = if game_over || !hold_available {
+                util::rgb_to_grayscale(held.rtype.color())
             } else {
-                next.rtype.color()
+                held.rtype.color()
             };
             canvas.draw(&mesh, draw_param.dest_rect(rect).color(color));
         }
@@ -223,7 +523,8 @@ fn draw_next(
     ctx: &mut Context,
     canvas: &mut Canvas,
     next_rustomino: &Option<Rustomino>,
-    next_rect: &Rect,
+    view_settings: &ViewSettings,
+    animation_time: f64,
     game_over: bool,
 ) -> GameResult {
     // create a mesh we'll reuse for each block
@@ -237,7 +538,11 @@ fn draw_next(
     let draw_param = graphics::DrawParam::new();
     if let Some(next) = next_rustomino {
         for block in next.blocks {
-            let rect = next_block_rect([block[0], block[1]], next_rect);
+            let rect = animated_block_rect(
+                next_block_rect([block[0], block[1]], view_settings),
+                &view_settings.preview_rect,
+                animation_time,
+            );
             let color = if game_over {
                 util::rgb_to_grayscale(next.rtype.color())
             } else {
@@ -249,13 +554,17 @@ fn draw_next(
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn draw_playing(
     ctx: &mut Context,
     canvas: &mut Canvas,
     playfield: &Playfield,
     next_rustomino: &Option<Rustomino>,
     hold_rustomino: &Option<Rustomino>,
+    hold_available: bool,
+    animations: &Animations,
     view_settings: &ViewSettings,
+    danger: bool,
     game_over: bool,
 ) -> GameResult {
     draw_playing_backgound(ctx, canvas, view_settings)?;
@@ -263,22 +572,26 @@ pub fn draw_playing(
         ctx,
         canvas,
         playfield,
-        &view_settings.staging_rect,
-        &view_settings.playfield_rect,
+        view_settings,
+        animations.spawn,
+        danger,
         game_over,
     )?;
     draw_hold(
         ctx,
         canvas,
         hold_rustomino,
-        &view_settings.hold_rect,
+        view_settings,
+        hold_available,
+        animations.hold,
         game_over,
     )?;
     draw_next(
         ctx,
         canvas,
         next_rustomino,
-        &view_settings.preview_rect,
+        view_settings,
+        animations.next,
         game_over,
     )?;
 
@@ -286,10 +599,12 @@ pub fn draw_playing(
 }
 
 pub fn draw_playing_text(
-    _ctx: &mut Context,
+    ctx: &mut Context,
     canvas: &mut Canvas,
     level: usize,
+    level_progress: (usize, usize),
     score: usize,
+    lock_timer: Option<LockTimer>,
     view_settings: &ViewSettings,
 ) -> GameResult {
     let mut title_text = graphics::Text::new("Blocks!");
@@ -297,20 +612,17 @@ pub fn draw_playing_text(
     let mut score_text = graphics::Text::new("Score:");
 
     let text_param = graphics::DrawParam::default();
+    let hud_font_scale = graphics::PxScale::from(UI_FONT_SIZE * view_settings.hud_scale);
 
     canvas.draw(
-        title_text
-            .set_font("04b30")
-            .set_scale(graphics::PxScale::from(UI_FONT_SIZE)),
+        title_text.set_font("04b30").set_scale(hud_font_scale),
         text_param
             .dest([view_settings.title_pos.x, view_settings.title_pos.y])
             .color(Color::new(1., 1., 1., 1.)),
     );
 
     canvas.draw(
-        level_text
-            .set_font("04b30")
-            .set_scale(graphics::PxScale::from(UI_FONT_SIZE)),
+        level_text.set_font("04b30").set_scale(hud_font_scale),
         text_param
             .dest([
                 view_settings.level_label_pos.x,
@@ -320,9 +632,7 @@ pub fn draw_playing_text(
     );
 
     canvas.draw(
-        score_text
-            .set_font("04b30")
-            .set_scale(graphics::PxScale::from(UI_FONT_SIZE)),
+        score_text.set_font("04b30").set_scale(hud_font_scale),
         text_param
             .dest([
                 view_settings.score_label_pos.x,
@@ -334,20 +644,424 @@ pub fn draw_playing_text(
     canvas.draw(
         graphics::Text::new(level.to_string())
             .set_font("04b30")
-            .set_scale(graphics::PxScale::from(UI_FONT_SIZE)),
+            .set_scale(hud_font_scale),
         text_param
             .dest([view_settings.level_pos.x, view_settings.level_pos.y])
             .color(Color::new(1., 1., 1., 1.)),
     );
+    // lines cleared toward the next level
+    canvas.draw(
+        graphics::Text::new(format!("{}/{}", level_progress.0, level_progress.1))
+            .set_font("04b30")
+            .set_scale(graphics::PxScale::from(
+                LEVEL_PROGRESS_FONT_SIZE * view_settings.hud_scale,
+            )),
+        text_param
+            .dest([
+                view_settings.level_progress_pos.x,
+                view_settings.level_progress_pos.y,
+            ])
+            .color(Color::new(1., 1., 1., 0.7)),
+    );
     canvas.draw(
         graphics::Text::new(score.to_string())
             .set_font("04b30")
-            .set_scale(graphics::PxScale::from(UI_FONT_SIZE)),
+            .set_scale(hud_font_scale),
         text_param
             .dest([view_settings.score_pos.x, view_settings.score_pos.y])
             .color(Color::new(1., 1., 1., 1.)),
     );
 
+    // the lock timer sits above the score like the level progress above the level
+    // a bar for the lockdown time left followed by the resets used
+    if let Some(lock_timer) = lock_timer {
+        let bar_h = LEVEL_PROGRESS_FONT_SIZE * view_settings.hud_scale;
+        let bar_w = LOCK_TIMER_BAR_WIDTH * view_settings.hud_scale;
+        let pos = view_settings.lock_timer_pos;
+        let outline = graphics::Mesh::new_rectangle(
+            ctx,
+            DrawMode::stroke(1.0),
+            Rect::new(pos.x, pos.y, bar_w, bar_h),
+            Color::new(1., 1., 1., 0.7),
+        )?;
+        canvas.draw(&outline, graphics::DrawParam::default());
+        if lock_timer.remaining > 0.0 {
+            let fill = graphics::Mesh::new_rectangle(
+                ctx,
+                DrawMode::fill(),
+                Rect::new(pos.x, pos.y, bar_w * lock_timer.remaining, bar_h),
+                Color::new(1., 1., 1., 0.7),
+            )?;
+            canvas.draw(&fill, graphics::DrawParam::default());
+        }
+        canvas.draw(
+            graphics::Text::new(format!(
+                "{}/{}",
+                lock_timer.resets_used, lock_timer.max_resets
+            ))
+            .set_font("04b30")
+            .set_scale(graphics::PxScale::from(bar_h)),
+            text_param
+                .dest([pos.x + bar_w + 10.0, pos.y])
+                .color(Color::new(1., 1., 1., 0.7)),
+        );
+    }
+
+    Ok(())
+}
+
+pub fn draw_popups(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    popups: &[Popup],
+    view_settings: &ViewSettings,
+    reduce_motion: bool,
+) -> GameResult {
+    let font_scale = graphics::PxScale::from(POPUP_FONT_SIZE);
+    let playfield_rect = &view_settings.playfield_rect;
+
+    for (i, popup) in popups.iter().enumerate() {
+        let mut text = graphics::Text::new(popup.text.as_str());
+        let scaled_text = text.set_font("04b30").set_scale(font_scale);
+        let glyph_pos = scaled_text.glyph_positions(ctx)?;
+        let text_width =
+            glyph_pos.last().unwrap().x - glyph_pos.first().unwrap().x + font_scale.x / 2.0;
+
+        // stack multiple popups so they don't overlap
+        let y = playfield_rect.y + playfield_rect.h / 3.0 + (font_scale.y * 1.5 * i as f32);
+        // popups are static and fully opaque when reducing motion
+        let (offset, alpha) = if reduce_motion {
+            (0.0, 1.0)
+        } else {
+            (popup.offset(), popup.alpha())
+        };
+
+        canvas.draw(
+            scaled_text,
+            graphics::DrawParam::default()
+                .dest([
+                    playfield_rect.x + playfield_rect.w / 2.0 - text_width / 2.0,
+                    y + offset,
+                ])
+                .color(Color::new(1., 1., 1., alpha)),
+        );
+    }
+
+    Ok(())
+}
+
+// covers the screen in black, fading in the new state as the opacity falls
+pub fn draw_transition(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    view_settings: &ViewSettings,
+    opacity: f32,
+) -> GameResult {
+    let overlay = graphics::Mesh::new_rectangle(
+        ctx,
+        DrawMode::fill(),
+        view_settings.view_rect,
+        Color::new(0.0, 0.0, 0.0, opacity),
+    )?;
+    canvas.draw(&overlay, graphics::DrawParam::default());
+    Ok(())
+}
+
+pub fn draw_countdown(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    text: &str,
+    view_settings: &ViewSettings,
+) -> GameResult {
+    let font_scale = graphics::PxScale::from(COUNTDOWN_FONT_SIZE);
+    let playfield_rect = &view_settings.playfield_rect;
+
+    let mut text = graphics::Text::new(text);
+    let scaled_text = text.set_font("04b30").set_scale(font_scale);
+    let glyph_pos = scaled_text.glyph_positions(ctx)?;
+    let text_width =
+        glyph_pos.last().unwrap().x - glyph_pos.first().unwrap().x + font_scale.x / 2.0;
+
+    canvas.draw(
+        scaled_text,
+        graphics::DrawParam::default()
+            .dest([
+                playfield_rect.x + playfield_rect.w / 2.0 - text_width / 2.0,
+                playfield_rect.y + playfield_rect.h / 2.0 - font_scale.y / 2.0,
+            ])
+            .color(Color::new(1., 1., 1., 1.)),
+    );
+
+    Ok(())
+}
+
+/// overlays the current control bindings without pausing the game
+pub fn draw_controls_help(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    input_map: &HashMap<Control, [Option<KeyCode>; 3]>,
+    view_settings: &ViewSettings,
+) -> GameResult {
+    let mut text = graphics::Text::new(key_map_lines(input_map).join("\n"));
+    let text = text
+        .set_font("04b30")
+        .set_scale(graphics::PxScale::from(CONTROLS_HELP_FONT_SIZE));
+    let text_size = text.measure(ctx)?;
+
+    // centre the panel over the playfield
+    let playfield_rect = &view_settings.playfield_rect;
+    let panel_rect = Rect::new(
+        playfield_rect.x + playfield_rect.w / 2.0 - text_size.x / 2.0 - CONTROLS_HELP_PADDING,
+        playfield_rect.y + playfield_rect.h / 2.0 - text_size.y / 2.0 - CONTROLS_HELP_PADDING,
+        text_size.x + CONTROLS_HELP_PADDING * 2.0,
+        text_size.y + CONTROLS_HELP_PADDING * 2.0,
+    );
+    let panel =
+        graphics::Mesh::new_rectangle(ctx, DrawMode::fill(), panel_rect, CONTROLS_HELP_COLOR)?;
+    canvas.draw(&panel, graphics::DrawParam::default());
+    canvas.draw(
+        text,
+        graphics::DrawParam::default()
+            .dest([
+                panel_rect.x + CONTROLS_HELP_PADDING,
+                panel_rect.y + CONTROLS_HELP_PADDING,
+            ])
+            .color(Color::new(1., 1., 1., 1.)),
+    );
+    Ok(())
+}
+
+// one line per control with its keys,
+// in a fixed order rather than the map's
+fn key_map_lines(input_map: &HashMap<Control, [Option<KeyCode>; 3]>) -> Vec<String> {
+    Control::iter()
+        .filter_map(|control| {
+            let keys = input_map
+                .get(&control)?
+                .iter()
+                .flatten()
+                .map(|key| format!("{:?}", key))
+                .collect::<Vec<_>>();
+            (!keys.is_empty()).then(|| format!("{:?}: {}", control, keys.join(", ")))
+        })
+        .collect()
+}
+
+/// shows the keys a key setting leads to below the options panel
+/// so they can be checked before leaving the options
+pub fn draw_key_map_preview(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    input_map: &HashMap<Control, [Option<KeyCode>; 3]>,
+    view_rect: &Rect,
+) -> GameResult {
+    let mut text = graphics::Text::new(key_map_lines(input_map).join("\n"));
+    let text = text
+        .set_font("04b30")
+        .set_scale(graphics::PxScale::from(KEY_MAP_PREVIEW_FONT_SIZE));
+    let text_size = text.measure(ctx)?;
+
+    // centred in the space between the options panel and the bottom of the view
+    let below_options = view_rect.h / 2. + (400. / 2.);
+    let panel_rect = Rect::new(
+        view_rect.w / 2.0 - text_size.x / 2.0 - CONTROLS_HELP_PADDING,
+        below_options + (view_rect.h - below_options) / 2.0
+            - text_size.y / 2.0
+            - CONTROLS_HELP_PADDING,
+        text_size.x + CONTROLS_HELP_PADDING * 2.0,
+        text_size.y + CONTROLS_HELP_PADDING * 2.0,
+    );
+    let panel =
+        graphics::Mesh::new_rectangle(ctx, DrawMode::fill(), panel_rect, CONTROLS_HELP_COLOR)?;
+    canvas.draw(&panel, graphics::DrawParam::default());
+    canvas.draw(
+        text,
+        graphics::DrawParam::default()
+            .dest([
+                panel_rect.x + CONTROLS_HELP_PADDING,
+                panel_rect.y + CONTROLS_HELP_PADDING,
+            ])
+            .color(Color::new(1., 1., 1., 1.)),
+    );
+    Ok(())
+}
+
+/// marks the columns the next block will spawn in along the top of the staging area
+pub fn draw_spawn_guide(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    next_rustomino: &Rustomino,
+    view_settings: &ViewSettings,
+) -> GameResult {
+    let mut color = next_rustomino.rtype.color();
+    color.a = SPAWN_GUIDE_ALPHA;
+    let guide_mesh = graphics::Mesh::new_rectangle(
+        ctx,
+        DrawMode::fill(),
+        Rect::new(0.0, 0.0, 1.0, 1.0),
+        Color::new(1.0, 1.0, 1.0, 1.0),
+    )?;
+
+    let mut columns = next_rustomino
+        .playfield_slots()
+        .iter()
+        .map(|block| block[0])
+        .collect::<Vec<_>>();
+    columns.sort();
+    columns.dedup();
+    for column in columns {
+        let block_rect = playfield_block_rect([column, 0], view_settings);
+        let rect = Rect::new(
+            block_rect.x,
+            view_settings.staging_rect.y,
+            block_rect.w,
+            SPAWN_GUIDE_HEIGHT,
+        );
+        canvas.draw(
+            &guide_mesh,
+            graphics::DrawParam::default().dest_rect(rect).color(color),
+        );
+    }
+    Ok(())
+}
+
+// shows how many blocks have been drawn since the last I block below the preview
+pub fn draw_drought(
+    _ctx: &mut Context,
+    canvas: &mut Canvas,
+    drought: u32,
+    view_settings: &ViewSettings,
+) -> GameResult {
+    let mut drought_text = graphics::Text::new(format!("I Drought: {}", drought));
+    let drought_text = drought_text
+        .set_font("04b30")
+        .set_scale(graphics::PxScale::from(DROUGHT_FONT_SIZE));
+    let preview_rect = view_settings.preview_rect;
+    canvas.draw(
+        drought_text,
+        graphics::DrawParam::default()
+            .dest([preview_rect.x, preview_rect.y + preview_rect.h + 10.0])
+            .color(Color::new(1., 1., 1., 1.)),
+    );
+    Ok(())
+}
+
+// shows how many holds are left under the hold limit below the hold box
+pub fn draw_holds_remaining(
+    _ctx: &mut Context,
+    canvas: &mut Canvas,
+    holds: u32,
+    view_settings: &ViewSettings,
+) -> GameResult {
+    let mut holds_text = graphics::Text::new(format!("Holds: {}", holds));
+    let holds_text = holds_text
+        .set_font("04b30")
+        .set_scale(graphics::PxScale::from(HOLDS_REMAINING_FONT_SIZE));
+    let hold_rect = view_settings.hold_rect;
+    // dimmed once the holds have run out, like the held block
+    let color = if holds == 0 {
+        Color::new(0.5, 0.5, 0.5, 1.)
+    } else {
+        Color::new(1., 1., 1., 1.)
+    };
+    canvas.draw(
+        holds_text,
+        graphics::DrawParam::default()
+            .dest([hold_rect.x, hold_rect.y + hold_rect.h + 10.0])
+            .color(color),
+    );
+    Ok(())
+}
+
+/// shade each slot by how often blocks have been locked in it
+/// compared to the most used slot
+pub fn draw_heatmap(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    lock_counts: &playfield::LockCounts,
+    view_settings: &ViewSettings,
+) -> GameResult {
+    let max = lock_counts.iter().flatten().copied().max().unwrap_or(0);
+    if max == 0 {
+        return Ok(());
+    }
+    // one mesh reused for each slot
+    let heat_mesh = graphics::Mesh::new_rectangle(
+        ctx,
+        DrawMode::fill(),
+        Rect::new(0.0, 0.0, 1.0, 1.0),
+        Color::new(1.0, 1.0, 1.0, 1.0),
+    )?;
+    for (y, row) in lock_counts
+        .iter()
+        .take(view_settings.drawn_rows)
+        .enumerate()
+    {
+        for (x, count) in row.iter().enumerate() {
+            if *count == 0 {
+                continue;
+            }
+            let rect = playfield_block_rect([x as i32, y as i32], view_settings);
+            let mut color = HEATMAP_COLOR;
+            color.a *= *count as f32 / max as f32;
+            canvas.draw(
+                &heat_mesh,
+                graphics::DrawParam::default().dest_rect(rect).color(color),
+            );
+        }
+    }
+    Ok(())
+}
+
+/// draws how many rows a hard drop would move the active block,
+/// centered on the ghost
+pub fn draw_drop_hint(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    playfield: &Playfield,
+    view_settings: &ViewSettings,
+) -> GameResult {
+    let distance = playfield.active_drop_distance();
+    // the ghost is hidden behind the active block when it can't drop
+    if distance == 0 {
+        return Ok(());
+    }
+    let Some(landing_slots) = playfield.active_landing_slots() else {
+        return Ok(());
+    };
+
+    // find the area covered by the ghost's blocks
+    let ghost_rect = landing_slots
+        .iter()
+        .map(|block| playfield_block_rect([block[0], block[1]], view_settings))
+        .reduce(|a, b| a.combine_with(b))
+        .unwrap();
+
+    let font_scale = graphics::PxScale::from(DROP_HINT_FONT_SIZE);
+    let mut text = graphics::Text::new(distance.to_string());
+    let scaled_text = text.set_font("04b30").set_scale(font_scale);
+    let glyph_pos = scaled_text.glyph_positions(ctx)?;
+    let text_width =
+        glyph_pos.last().unwrap().x - glyph_pos.first().unwrap().x + font_scale.x / 2.0;
+    let dest = [
+        ghost_rect.x + ghost_rect.w / 2.0 - text_width / 2.0,
+        ghost_rect.y + ghost_rect.h / 2.0 - font_scale.y / 2.0,
+    ];
+
+    // draw a shadow so the number stands out against the ghost outline
+    canvas.draw(
+        scaled_text,
+        graphics::DrawParam::default()
+            .dest([dest[0] + 1.0, dest[1] + 1.0])
+            .color(DROP_HINT_SHADOW_COLOR),
+    );
+    canvas.draw(
+        scaled_text,
+        graphics::DrawParam::default()
+            .dest(dest)
+            .color(Color::new(1., 1., 1., 1.)),
+    );
+
     Ok(())
 }
 
@@ -399,7 +1113,28 @@ fn draw_menu_text<T: Menu>(
     // draw title
     canvas.draw(scaled_title, title_draw_param);
 
-    for (i, item) in menu_state.items().iter().enumerate() {
+    // scroll the items when there are more than fit below the title
+    let items_y = view_settings.view_rect.h / 1.9;
+    let visible = ((view_settings.view_rect.h - items_y - MENU_BOTTOM_MARGIN) / font_scale.y)
+        .max(1.0) as usize;
+    let visible_items = menu_state.visible_items(visible);
+    draw_scroll_indicators(
+        ctx,
+        canvas,
+        view_settings.view_rect.w / 2.0,
+        items_y,
+        items_y + font_scale.y * visible_items.len() as f32,
+        visible_items.start > 0,
+        visible_items.end < menu_state.items().len(),
+    )?;
+
+    for (i, item) in menu_state
+        .items()
+        .iter()
+        .enumerate()
+        .take(visible_items.end)
+        .skip(visible_items.start)
+    {
         let mut item = item.clone();
         let scaled_text = item.set_font("04b30").set_scale(font_scale);
         let glyph_pos = scaled_text.glyph_positions(ctx)?;
@@ -414,7 +1149,7 @@ fn draw_menu_text<T: Menu>(
         let menu_item_draw_param = graphics::DrawParam::default()
             .dest([
                 x_pos,
-                view_settings.view_rect.h / 1.9 + (menu_item_height * (i as f32)),
+                items_y + (menu_item_height * ((i - visible_items.start) as f32)),
             ])
             .color(Color::new(1., 1., 1., 1.));
         canvas.draw(scaled_text, menu_item_draw_param);
@@ -436,11 +1171,92 @@ pub fn draw_menu(
     Ok(())
 }
 
-pub fn draw_gameover(ctx: &mut Context, canvas: &mut Canvas, view_rect: &Rect) -> GameResult {
+/// ask before quitting from the main menu
+pub fn draw_confirm_quit(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    confirm_quit_state: &menus::ConfirmQuitState,
+    view_settings: &ViewSettings,
+) -> GameResult {
+    draw_menu_text(ctx, canvas, confirm_quit_state, view_settings, "Quit?")?;
+    Ok(())
+}
+
+/// lists the scenarios that can be practiced from
+pub fn draw_scenarios(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    scenario_state: &menus::ScenarioState,
+    view_settings: &ViewSettings,
+) -> GameResult {
+    draw_menu_text(ctx, canvas, scenario_state, view_settings, "Practice")?;
+    Ok(())
+}
+
+/// shows the best clears along the bottom of the main menu
+pub fn draw_records(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    records: &Records,
+    session_records: &Records,
+    view_settings: &ViewSettings,
+) -> GameResult {
+    if *records == Records::default() {
+        return Ok(());
+    }
+    let view_rect = &view_settings.view_rect;
+    let summary = |records: &Records| {
+        format!(
+            "Combo {}  B2B {}  Lines {}",
+            records.longest_combo, records.best_back_to_back, records.most_lines
+        )
+    };
+    let mut records_text = graphics::Text::new(format!(
+        "Best: {}    Session: {}",
+        summary(records),
+        summary(session_records)
+    ));
+    let records_text = records_text
+        .set_font("04b30")
+        .set_scale(graphics::PxScale::from(RUN_INFO_FONT_SIZE));
+    let records_width = records_text.measure(ctx)?.x;
+    canvas.draw(
+        records_text,
+        graphics::DrawParam::default()
+            .dest([
+                view_rect.w / 2.0 - records_width / 2.0,
+                view_rect.h - MENU_BOTTOM_MARGIN + 4.0,
+            ])
+            .color(RUN_INFO_COLOR),
+    );
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn draw_gameover(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    view_settings: &ViewSettings,
+    game_over_time: f64,
+    reason: Option<&str>,
+    run_info: &str, // the seed and mode so the game can be shared and replayed
+    game_over_menu: &menus::GameOverState,
+    reduce_motion: bool,
+) -> GameResult {
+    let view_rect = &view_settings.view_rect;
     let gameover_overlay =
         graphics::Mesh::new_rectangle(ctx, DrawMode::fill(), *view_rect, PAUSED_OVERLAY_COLOR)?;
     canvas.draw(&gameover_overlay, graphics::DrawParam::default());
 
+    // flash the playfield border when the game ends
+    // or show a static tint when reducing motion
+    let border_visible = game_over_time < GAME_OVER_FLASH_TIME
+        && (reduce_motion
+            || ((game_over_time / GAME_OVER_FLASH_INTERVAL) as u32).is_multiple_of(2));
+    if border_visible {
+        draw_playfield_border(ctx, canvas, view_settings, GAME_OVER_BORDER_COLOR)?;
+    }
+
     let slow_wobble = util::slow_wobble(ctx.time.time_since_start().as_secs_f32());
 
     let mut scaled_text = graphics::Text::new("Game Over!");
@@ -460,10 +1276,229 @@ pub fn draw_gameover(ctx: &mut Context, canvas: &mut Canvas, view_rect: &Rect) -
             ])
             .color(Color::new(1., 1., 1., 1.)),
     );
+
+    // explain why the game ended below the game over text
+    if let Some(reason) = reason {
+        let mut reason_text = graphics::Text::new(reason);
+        let reason_text = reason_text
+            .set_font("04b30")
+            .set_scale(graphics::PxScale::from(UI_FONT_SIZE));
+        let reason_width = reason_text.measure(ctx)?.x;
+        canvas.draw(
+            reason_text,
+            graphics::DrawParam::default()
+                .dest([
+                    view_rect.w / 2.0 - reason_width / 2.0,
+                    view_rect.h / 2.0 + 40.0,
+                ])
+                .color(Color::new(1., 1., 1., 1.)),
+        );
+    }
+
+    let mut run_info_text = graphics::Text::new(run_info);
+    let run_info_text = run_info_text
+        .set_font("04b30")
+        .set_scale(graphics::PxScale::from(RUN_INFO_FONT_SIZE));
+    let run_info_width = run_info_text.measure(ctx)?.x;
+    canvas.draw(
+        run_info_text,
+        graphics::DrawParam::default()
+            .dest([
+                view_rect.w / 2.0 - run_info_width / 2.0,
+                view_rect.h / 2.0 + 80.0,
+            ])
+            .color(RUN_INFO_COLOR),
+    );
+
+    // a small menu to leave the game over screen, the selected entry wobbles
+    let fast_wobble = util::fast_wobble(ctx.time.time_since_start().as_secs_f32());
+    for (i, item) in game_over_menu.items().iter().enumerate() {
+        let mut item = item.clone();
+        let item_text = item
+            .set_font("04b30")
+            .set_scale(graphics::PxScale::from(GAME_OVER_MENU_FONT_SIZE));
+        let item_width = item_text.measure(ctx)?.x;
+        let wobble = if game_over_menu.selected() == i && !reduce_motion {
+            fast_wobble * 5.0
+        } else {
+            0.0
+        };
+        let color = if game_over_menu.selected() == i {
+            Color::new(1., 1., 1., 1.)
+        } else {
+            RUN_INFO_COLOR
+        };
+        canvas.draw(
+            item_text,
+            graphics::DrawParam::default()
+                .dest([
+                    view_rect.w / 2.0 - item_width / 2.0 + wobble,
+                    view_rect.h / 2.0 + GAME_OVER_MENU_TOP + GAME_OVER_MENU_FONT_SIZE * i as f32,
+                ])
+                .color(color),
+        );
+    }
     Ok(())
 }
 
-pub fn draw_options(ctx: &mut Context, canvas: &mut Canvas, view_rect: &Rect) -> GameResult {
+/// lists the most recent moves down the left edge of the view
+pub fn draw_move_history(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    lines: &[String],
+    view_settings: &ViewSettings,
+) -> GameResult {
+    if lines.is_empty() {
+        return Ok(());
+    }
+    let mut text = graphics::Text::new(lines.join("\n"));
+    let text = text
+        .set_font("04b30")
+        .set_scale(graphics::PxScale::from(MOVE_HISTORY_FONT_SIZE));
+    let text_size = text.measure(ctx)?;
+
+    let panel_rect = Rect::new(
+        HUD_MARGIN,
+        view_settings.view_rect.h / 2.0 - text_size.y / 2.0 - CONTROLS_HELP_PADDING,
+        text_size.x + CONTROLS_HELP_PADDING * 2.0,
+        text_size.y + CONTROLS_HELP_PADDING * 2.0,
+    );
+    let panel =
+        graphics::Mesh::new_rectangle(ctx, DrawMode::fill(), panel_rect, CONTROLS_HELP_COLOR)?;
+    canvas.draw(&panel, graphics::DrawParam::default());
+    canvas.draw(
+        text,
+        graphics::DrawParam::default()
+            .dest([
+                panel_rect.x + CONTROLS_HELP_PADDING,
+                panel_rect.y + CONTROLS_HELP_PADDING,
+            ])
+            .color(Color::new(1., 1., 1., 1.)),
+    );
+    Ok(())
+}
+
+/// shows the active block's translation and facing in the top right of the view
+pub fn draw_active_position(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    active: &Rustomino,
+    view_settings: &ViewSettings,
+) -> GameResult {
+    let mut text = graphics::Text::new(format!(
+        "{:?} x: {} y: {} facing: {:?}",
+        active.rtype,
+        active.translation.x,
+        active.translation.y,
+        active.rotation.direction()
+    ));
+    let text = text
+        .set_font("04b30")
+        .set_scale(graphics::PxScale::from(ACTIVE_POSITION_FONT_SIZE));
+    let text_size = text.measure(ctx)?;
+
+    let panel_rect = Rect::new(
+        view_settings.view_rect.w - HUD_MARGIN - text_size.x - CONTROLS_HELP_PADDING * 2.0,
+        HUD_MARGIN,
+        text_size.x + CONTROLS_HELP_PADDING * 2.0,
+        text_size.y + CONTROLS_HELP_PADDING * 2.0,
+    );
+    let panel =
+        graphics::Mesh::new_rectangle(ctx, DrawMode::fill(), panel_rect, CONTROLS_HELP_COLOR)?;
+    canvas.draw(&panel, graphics::DrawParam::default());
+    canvas.draw(
+        text,
+        graphics::DrawParam::default()
+            .dest([
+                panel_rect.x + CONTROLS_HELP_PADDING,
+                panel_rect.y + CONTROLS_HELP_PADDING,
+            ])
+            .color(Color::new(1., 1., 1., 1.)),
+    );
+    Ok(())
+}
+
+/// celebrate a new high score above the game over text
+/// the banner pulses between gold and white unless reducing motion
+pub fn draw_new_high_score(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    view_settings: &ViewSettings,
+    reduce_motion: bool,
+) -> GameResult {
+    let view_rect = &view_settings.view_rect;
+    let color = if reduce_motion {
+        HIGH_SCORE_COLOR
+    } else {
+        let pulse = (util::fast_wobble(ctx.time.time_since_start().as_secs_f32()) + 1.0) / 2.0;
+        Color::new(
+            HIGH_SCORE_COLOR.r + (1.0 - HIGH_SCORE_COLOR.r) * pulse,
+            HIGH_SCORE_COLOR.g + (1.0 - HIGH_SCORE_COLOR.g) * pulse,
+            HIGH_SCORE_COLOR.b + (1.0 - HIGH_SCORE_COLOR.b) * pulse,
+            1.0,
+        )
+    };
+    let mut high_score_text = graphics::Text::new("New High Score!");
+    let high_score_text = high_score_text
+        .set_font("04b30")
+        .set_scale(graphics::PxScale::from(HIGH_SCORE_FONT_SIZE));
+    let text_width = high_score_text.measure(ctx)?.x;
+    canvas.draw(
+        high_score_text,
+        graphics::DrawParam::default()
+            .dest([
+                view_rect.w / 2.0 - text_width / 2.0,
+                view_rect.h / 2.0 - 90.0,
+            ])
+            .color(color),
+    );
+    Ok(())
+}
+
+// outlines the playfield and staging area
+fn draw_playfield_border(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    view_settings: &ViewSettings,
+    color: Color,
+) -> GameResult {
+    let playfield_rect = &view_settings.playfield_rect;
+    let staging_rect = &view_settings.staging_rect;
+    let border_rect = Rect::new(
+        playfield_rect.x - PLAYFIELD_BORDER_WIDTH / 2.0,
+        staging_rect.y - PLAYFIELD_BORDER_WIDTH / 2.0,
+        playfield_rect.w + PLAYFIELD_BORDER_WIDTH,
+        playfield_rect.y + playfield_rect.h - staging_rect.y + PLAYFIELD_BORDER_WIDTH,
+    );
+    let border = graphics::Mesh::new_rectangle(
+        ctx,
+        DrawMode::stroke(PLAYFIELD_BORDER_WIDTH),
+        border_rect,
+        color,
+    )?;
+    canvas.draw(&border, graphics::DrawParam::default());
+    Ok(())
+}
+
+/// pulse the playfield border on each metronome beat
+/// phase is how far through the current beat the metronome is, from 0.0 to 1.0
+pub fn draw_metronome(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    phase: f64,
+    view_settings: &ViewSettings,
+) -> GameResult {
+    let mut color = METRONOME_COLOR;
+    color.a *= 1.0 - phase as f32;
+    draw_playfield_border(ctx, canvas, view_settings, color)
+}
+
+pub fn draw_options(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    options_state: &menus::OptionsState,
+    view_rect: &Rect,
+) -> GameResult {
     let options_background_shadow = graphics::Mesh::new_rectangle(
         ctx,
         DrawMode::fill(),
@@ -490,6 +1525,8 @@ pub fn draw_options(ctx: &mut Context, canvas: &mut Canvas, view_rect: &Rect) ->
     )?;
     canvas.draw(&options_background_mesh, graphics::DrawParam::default());
 
+    draw_options_text(ctx, canvas, options_state, view_rect)?;
+
     //     draw_text_ex(
     //         "Controls:",
     //         305.,
@@ -556,6 +1593,112 @@ pub fn draw_options(ctx: &mut Context, canvas: &mut Canvas, view_rect: &Rect) ->
     Ok(())
 }
 
+fn draw_options_text(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    options_state: &menus::OptionsState,
+    view_rect: &Rect,
+) -> GameResult {
+    let fast_wobble = util::fast_wobble(ctx.time.time_since_start().as_secs_f32());
+
+    // options are listed from the top left of the options panel
+    let options_x = view_rect.w / 2. - (600. / 2.) + 30.;
+    let options_y = view_rect.h / 2. - (400. / 2.) + 30.;
+    let item_height = UI_FONT_SIZE * 1.5;
+    let values_right = view_rect.w / 2. + (600. / 2.) - 30.;
+
+    // scroll the options when there are more than fit in the panel
+    let visible = ((400. - 60.) / item_height) as usize;
+    let visible_items = options_state.visible_items(visible);
+    draw_scroll_indicators(
+        ctx,
+        canvas,
+        view_rect.w / 2.,
+        options_y,
+        options_y + item_height * visible_items.len() as f32,
+        visible_items.start > 0,
+        visible_items.end < options_state.items().len(),
+    )?;
+
+    for (i, item) in options_state
+        .items()
+        .iter()
+        .enumerate()
+        .take(visible_items.end)
+        .skip(visible_items.start)
+    {
+        let mut item = item.clone();
+        let item_y = options_y + (item_height * ((i - visible_items.start) as f32));
+        let x_pos = if options_state.selected() == i {
+            options_x + fast_wobble * 5.0
+        } else {
+            options_x
+        };
+        canvas.draw(
+            item.set_font("04b30")
+                .set_scale(graphics::PxScale::from(UI_FONT_SIZE)),
+            graphics::DrawParam::default()
+                .dest([x_pos, item_y])
+                .color(Color::new(1., 1., 1., 1.)),
+        );
+
+        // values are right aligned against the panel edge
+        let mut value = options_state.values()[i].clone();
+        let value = value
+            .set_font("04b30")
+            .set_scale(graphics::PxScale::from(UI_FONT_SIZE));
+        let value_width = value.measure(ctx)?.x;
+        canvas.draw(
+            value,
+            graphics::DrawParam::default()
+                .dest([values_right - value_width + x_pos - options_x, item_y])
+                .color(OPTIONS_VALUE_COLOR),
+        );
+    }
+    Ok(())
+}
+
+// draws arrows above and below a list of menu items
+// when some of the items are scrolled out of view
+fn draw_scroll_indicators(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    center_x: f32,
+    top_y: f32,
+    bottom_y: f32,
+    more_above: bool,
+    more_below: bool,
+) -> GameResult {
+    let size = SCROLL_INDICATOR_SIZE;
+    if more_above {
+        let arrow = graphics::Mesh::new_polygon(
+            ctx,
+            DrawMode::fill(),
+            &[
+                Vec2::new(center_x - size, top_y - 4.0),
+                Vec2::new(center_x + size, top_y - 4.0),
+                Vec2::new(center_x, top_y - 4.0 - size),
+            ],
+            Color::new(1., 1., 1., 1.),
+        )?;
+        canvas.draw(&arrow, graphics::DrawParam::default());
+    }
+    if more_below {
+        let arrow = graphics::Mesh::new_polygon(
+            ctx,
+            DrawMode::fill(),
+            &[
+                Vec2::new(center_x - size, bottom_y + 4.0),
+                Vec2::new(center_x + size, bottom_y + 4.0),
+                Vec2::new(center_x, bottom_y + 4.0 + size),
+            ],
+            Color::new(1., 1., 1., 1.),
+        )?;
+        canvas.draw(&arrow, graphics::DrawParam::default());
+    }
+    Ok(())
+}
+
 pub fn draw_paused(
     ctx: &mut Context,
     canvas: &mut Canvas,
@@ -584,31 +1727,89 @@ pub fn draw_paused_background(
     Ok(())
 }
 
-fn next_block_rect(block: [i32; 2], preview_rect: &Rect) -> Rect {
+fn next_block_rect(block: [i32; 2], view_settings: &ViewSettings) -> Rect {
+    let preview_rect = &view_settings.preview_rect;
+    let block_padding = view_settings.theme.block_padding;
     // block[x,y] absolute units
-    let x = preview_rect.x + (block[0] as f32 * (BLOCK_SIZE + BLOCK_PADDING)) + 1.0;
+    let x = preview_rect.x + block_padding + (block[0] as f32 * (BLOCK_SIZE + block_padding));
     // get bottom left of playfield_rect
-    let y = preview_rect.y + preview_rect.h - (block[1] as f32 * (BLOCK_SIZE + BLOCK_PADDING));
+    let y = preview_rect.y + preview_rect.h - (block[1] as f32 * (BLOCK_SIZE + block_padding));
 
     Rect::new(x, y, BLOCK_SIZE, BLOCK_SIZE)
 }
 
-fn hold_block_rect(block: [i32; 2], hold_rect: &Rect) -> Rect {
+/// scales and slides a block into its box while the box animation plays
+fn animated_block_rect(rect: Rect, box_rect: &Rect, animation_time: f64) -> Rect {
+    let progress = (animation_time / BOX_ANIMATION_TIME).clamp(0.0, 1.0) as f32;
+    // ease out so the block settles gently
+    let eased = 1.0 - (1.0 - progress).powi(3);
+    let scale = BOX_ANIMATION_START_SCALE + (1.0 - BOX_ANIMATION_START_SCALE) * eased;
+    let slide = BOX_ANIMATION_SLIDE * (1.0 - eased);
+
+    // scale around the center of the box
+    let center_x = box_rect.x + box_rect.w / 2.0;
+    let center_y = box_rect.y + box_rect.h / 2.0;
+    Rect::new(
+        center_x + (rect.x - center_x) * scale,
+        center_y + (rect.y - center_y) * scale - slide,
+        rect.w * scale,
+        rect.h * scale,
+    )
+}
+
+fn hold_block_rect(block: [i32; 2], view_settings: &ViewSettings) -> Rect {
+    let hold_rect = &view_settings.hold_rect;
+    let block_padding = view_settings.theme.block_padding;
     // block[x,y] absolute units
-    let x = hold_rect.x + (block[0] as f32 * (BLOCK_SIZE + BLOCK_PADDING)) + 1.0;
+    let x = hold_rect.x + block_padding + (block[0] as f32 * (BLOCK_SIZE + block_padding));
     // get bottom left of playfield_rect
-    let y = hold_rect.y + hold_rect.h - (block[1] as f32 * (BLOCK_SIZE + BLOCK_PADDING));
+    let y = hold_rect.y + hold_rect.h - (block[1] as f32 * (BLOCK_SIZE + block_padding));
 
     Rect::new(x, y, BLOCK_SIZE, BLOCK_SIZE)
 }
 
-fn playfield_block_rect(block: [i32; 2], staging_rect: &Rect, playfield_rect: &Rect) -> Rect {
+// moves a piece of text of the given size so it sits inside the view
+// preferring the top left when the view is too small to fit it
+fn clamp_to_view(pos: Vec2, size: Vec2, view_rect: &Rect) -> Vec2 {
+    let max_x = (view_rect.w - HUD_MARGIN - size.x).max(HUD_MARGIN);
+    let max_y = (view_rect.h - HUD_MARGIN - size.y).max(HUD_MARGIN);
+    Vec2::new(
+        pos.x.clamp(HUD_MARGIN, max_x),
+        pos.y.clamp(HUD_MARGIN, max_y),
+    )
+}
+
+// the rows of the playfield that are drawn, from the bottom up
+// rows above the playfield are clipped when the staging area is hidden
+fn visible_slots<'a>(
+    playfield: &'a Playfield,
+    view_settings: &ViewSettings,
+) -> impl Iterator<Item = (usize, &'a [SlotState; playfield::PLAYFIELD_SLOTS[0]])> {
+    playfield
+        .slots
+        .iter()
+        .take(view_settings.drawn_rows)
+        .enumerate()
+}
+
+fn playfield_block_rect(block: [i32; 2], view_settings: &ViewSettings) -> Rect {
+    let staging_rect = &view_settings.staging_rect;
+    let playfield_rect = &view_settings.playfield_rect;
+    let block_padding = view_settings.theme.block_padding;
     // block[x,y] absolute units
-    let x = staging_rect.x + (block[0] as f32 * (BLOCK_SIZE + BLOCK_PADDING)) + 1.0;
-    // get bottom left of playfield_rect
-    let y = playfield_rect.y + playfield_rect.h
-        - ((block[1] + 1) as f32 * (BLOCK_SIZE + BLOCK_PADDING))
-        - 1.0;
+    // each block is a whole BLOCK_SIZE + block_padding step from the edges of its area
+    // so a full row has exactly block_padding between blocks and at both ends
+    let x = staging_rect.x + block_padding + (block[0] as f32 * (BLOCK_SIZE + block_padding));
+    // blocks in the buffer rows are placed from the bottom of the staging area
+    // so they line up with it whether or not it's separate from the playfield
+    let buffer_row = playfield::PLAYFIELD_SLOTS[1] as i32 - 2;
+    let y = if block[1] >= buffer_row {
+        staging_rect.y + staging_rect.h
+            - ((block[1] - buffer_row + 1) as f32 * (BLOCK_SIZE + block_padding))
+    } else {
+        // get bottom left of playfield_rect
+        playfield_rect.y + playfield_rect.h - ((block[1] + 1) as f32 * (BLOCK_SIZE + block_padding))
+    };
 
     Rect::new(x, y, BLOCK_SIZE, BLOCK_SIZE)
 }
@@ -1,6 +1,6 @@
 use ggez::{
     audio::{self, SoundSource},
-    event::EventHandler,
+    event::{Button, EventHandler, GamepadId},
     glam::IVec2,
     graphics::{self},
     input::keyboard::KeyCode,
@@ -10,33 +10,189 @@ use ggez::{
 use strum::IntoEnumIterator;
 
 use crate::{
-    controls::{self, Control, GameControls},
-    draw::{self, BACKGROUND_COLOR},
-    menus::{self, Menu},
-    playfield::{Playfield, TranslationDirection, PLAYFIELD_SIZE},
-    rustomino::{Rotation, Rustomino, RustominoBag, RustominoState},
+    board_code,
+    controls::{self, Control, GameControls, KeyPreset, MenuAction},
+    demo::DemoPlayer,
+    draw,
+    garbage::GarbageSchedule,
+    high_score,
+    menus::{self, GameOverItem, Menu, MenuItem, OptionsItem},
+    playfield::{
+        self, LockCounts, Playfield, SlotState, TSpin, TranslationDirection, PLAYFIELD_SIZE,
+        PLAYFIELD_SLOTS,
+    },
+    popups::Popup,
+    profile,
+    records::Records,
+    rustomino::{Rotation, Rustomino, RustominoBag, RustominoState, RustominoType},
+    savegame::SavedGame,
+    scenario::Scenario,
+    scoring::ScoringConfig,
+    settings::{
+        self, GravityCurve, HoldLimitScope, LastMode, LockdownReset, Settings, SoftDropSpeed,
+    },
     util::variants_equal,
 };
 
-use std::{f64::consts::E, time};
+use std::{collections::VecDeque, f64::consts::E, path::PathBuf};
 
 // GAMEPLAY CONSTANTS
 const GRAVITY_NUMERATOR: f64 = 1.0;
 const GRAVITY_FACTOR: f64 = 0.1; // used to slow or increase gravity factor
-const STARTING_LEVEL: usize = 1;
+pub const STARTING_LEVEL: usize = 1;
 const LINES_PER_LEVEL: usize = 10; // number of lines that need to be cleared before level advances
-const LOCKDOWN_DELAY: f64 = 0.5; // how long to wait before locking block (Tetris Guideline)
-const LOCKDOWN_MAX_RESETS: u32 = 15; // maximum number of times the lockdown timer can be reset (Tetris Guideline)
-
-// SCORING CONSTANTS
-const SINGLE_LINE_SCORE: usize = 100;
-const TRIPLE_LINE_SCORE: usize = 500;
-const DOUBLE_LINE_SCORE: usize = 300;
-const QUAD_SCORE: usize = 800;
+const COUNTDOWN_READY_TIME: f64 = 1.2; // how long "Ready?" is shown before a new game
+const COUNTDOWN_GO_TIME: f64 = 0.8; // how long "Go!" is shown before a new game
+const STICKY_ROTATION_TIME: f64 = 0.15; // how long a blocked rotation is retried with sticky rotation
+
+// DEBUG CONSTANTS
+const SNAPSHOT_FILE: &str = "blocks_snapshot.txt";
+const SNAPSHOT_UPCOMING: usize = 14; // blocks after the next one listed in a snapshot, two bags
+const SLOW_MOTION_FACTOR: u32 = 10; // only one in this many updates runs in slow motion
+const CAPTURE_INTERVAL: u32 = 2; // only one in this many frames is captured
+const CAPTURE_MAX_FRAMES: u32 = 600; // capturing stops on its own after this many frames are saved
+const MOVE_HISTORY_LEN: usize = 20; // recent moves kept for the move history overlay
 
 // ASSET CONSTANTS
 const MUSIC_VOL: f32 = 0.1;
 const MUSIC_VOLUME_CHANGE: f32 = 0.01;
+const MUSIC_VOLUME_RAMP_TIME: f64 = 0.1; // how long the music takes to glide to a new volume
+const MUSIC_CROSSFADE_TIME: f64 = 1.0; // how long it takes to switch between the menu and gameplay music
+const TRANSITION_TIME: f64 = 0.3; // how long the screen takes to fade in after a change of state
+const LOCK_PITCH_RANGE: f32 = 0.15; // how much higher the lock sound is at the top of the playfield
+
+/// developer controls for stepping through the game one update at a time
+/// only available in debug builds
+#[derive(Debug, Default)]
+struct DebugStepping {
+    frozen: bool,      // stop the game updating on its own
+    steps: u32,        // updates requested while frozen
+    slow_motion: bool, // only run one in SLOW_MOTION_FACTOR updates
+    ticks: u32,        // updates skipped in slow motion
+}
+
+impl DebugStepping {
+    // returns true if this update should be skipped
+    fn skip_update(&mut self) -> bool {
+        if self.frozen {
+            if self.steps == 0 {
+                return true;
+            }
+            self.steps -= 1;
+            return false;
+        }
+        if self.slow_motion {
+            self.ticks = (self.ticks + 1) % SLOW_MOTION_FACTOR;
+            return self.ticks != 0;
+        }
+        false
+    }
+
+    // F10 freezes the game, F11 advances it one update, F12 toggles slow motion
+    fn key_down(&mut self, keycode: KeyCode) {
+        match keycode {
+            KeyCode::F10 => {
+                self.frozen = !self.frozen;
+                self.steps = 0;
+                log::info!("debug stepping frozen: {}", self.frozen);
+            }
+            KeyCode::F11 if self.frozen => {
+                self.steps += 1;
+                log::info!("debug stepping one update");
+            }
+            KeyCode::F12 => {
+                self.slow_motion = !self.slow_motion;
+                log::info!("debug slow motion: {}", self.slow_motion);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// developer tool that saves the screen to a numbered sequence of png files
+/// for bug reports and clips, only available in debug builds
+#[derive(Debug, Default)]
+struct FrameCapture {
+    dir: Option<String>, // where the current capture is being saved, None when not capturing
+    frames: u32,         // frames skipped since the last one was saved
+    saved: u32,          // frames saved since the capture started
+}
+
+impl FrameCapture {
+    // F8 starts and stops capturing into a new directory under the output directory
+    fn toggle(&mut self, output_dir: &str) {
+        if self.dir.is_some() {
+            self.stop();
+            return;
+        }
+        let started = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|time| time.as_secs())
+            .unwrap_or_default();
+        let dir = format!("/{}/capture_{}", output_dir.trim_matches('/'), started);
+        log::info!("started capturing frames to {}", dir);
+        *self = FrameCapture {
+            dir: Some(dir),
+            ..Default::default()
+        };
+    }
+
+    fn stop(&mut self) {
+        if let Some(dir) = self.dir.take() {
+            log::info!("stopped capturing, saved {} frames to {}", self.saved, dir);
+        }
+    }
+
+    // saves the last frame drawn, it's called before the next frame is drawn over it
+    fn capture(&mut self, ctx: &Context) -> GameResult {
+        let Some(dir) = &self.dir else {
+            return Ok(());
+        };
+        self.frames = (self.frames + 1) % CAPTURE_INTERVAL;
+        if self.frames != 0 {
+            return Ok(());
+        }
+        if self.saved == 0 {
+            ctx.fs.create_dir(dir)?;
+        }
+        let path = format!("{}/frame_{:05}.png", dir, self.saved);
+        ctx.gfx
+            .frame()
+            .encode(ctx, graphics::ImageEncodingFormat::Png, &path)?;
+        log::debug!("saved frame {}", path);
+        self.saved += 1;
+        if self.saved >= CAPTURE_MAX_FRAMES {
+            self.stop();
+        }
+        Ok(())
+    }
+}
+
+/// developer overlay listing the most recent moves and when they happened
+/// for looking into control and lockdown reports without reading the log
+#[derive(Debug, Default)]
+struct MoveHistory {
+    visible: bool,                  // F6 shows and hides the overlay in debug builds
+    moves: VecDeque<(f64, String)>, // play time and a description of each move, oldest first
+}
+
+impl MoveHistory {
+    fn record(&mut self, play_time: f64, action: String) {
+        if self.moves.len() == MOVE_HISTORY_LEN {
+            self.moves.pop_front();
+        }
+        self.moves.push_back((play_time, action));
+    }
+
+    // the newest moves first
+    fn lines(&self) -> Vec<String> {
+        self.moves
+            .iter()
+            .rev()
+            .map(|(time, action)| format!("{:7.2}s {}", time, action))
+            .collect()
+    }
+}
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum GameState {
@@ -48,9 +204,70 @@ pub enum GameState {
     Quit,
 }
 
+/// the kind of game being played
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameMode {
+    Marathon,
+    Trainer(RustominoType), // only one block type is dealt
+    Demo,
+    Practice, // started from a scenario file
+}
+
+impl GameMode {
+    pub fn name(&self) -> String {
+        match self {
+            GameMode::Marathon => "Marathon".to_string(),
+            GameMode::Trainer(rtype) => format!("{:?} Trainer", rtype),
+            GameMode::Demo => "Demo".to_string(),
+            GameMode::Practice => "Practice".to_string(),
+        }
+    }
+
+    /// only marathon games count toward the high score and records,
+    /// the trainer, demos and practice scenarios don't start from an even footing
+    pub fn counts_for_records(&self) -> bool {
+        *self == GameMode::Marathon
+    }
+}
+
+/// why the game ended, named after the Tetris Guideline terms
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameOverReason {
+    BlockOut, // a new block collided with the stack when it spawned
+    LockOut,  // a block locked entirely above the playfield
+    TopOut,   // the stack was pushed out of the top of the playfield
+}
+
+impl GameOverReason {
+    pub fn description(&self) -> &'static str {
+        match self {
+            GameOverReason::BlockOut => "No room for the next block",
+            GameOverReason::LockOut => "Block locked above the playfield",
+            GameOverReason::TopOut => "Stack pushed out the top",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SoundEffect {
+    LevelUp,
+    Lock { pitch: f32 },
+    Tick,
+    ResetsExhausted,
+}
+
 pub struct Assets {
     pub music_1: audio::Source,
+    pub menu_music: Option<audio::Source>, // music_1 plays everywhere if this is missing
     pub game_over: audio::Source,
+    pub level_up: audio::Source,
+    pub lock: Option<audio::Source>,
+    pub danger: Option<audio::Source>, // loops while the stack is near the top
+    pub tick: Option<audio::Source>,   // the practice metronome's beat
+    pub high_score: Option<audio::Source>, // replaces game_over after a new best
+    pub resets_exhausted: Option<audio::Source>, // a block was forced to lock after its last reset
+    pub background: Option<graphics::Image>, // drawn behind everything, scaled to cover the window
 }
 
 impl Assets {
@@ -59,9 +276,110 @@ impl Assets {
         let mut music_1 = audio::Source::new(ctx, "/music_1.ogg")?;
         music_1.set_volume(MUSIC_VOL);
         music_1.set_repeat(true);
+        let menu_music = match audio::Source::new(ctx, "/music_menu.wav") {
+            Ok(mut menu_music) => {
+                menu_music.set_repeat(true);
+                Some(menu_music)
+            }
+            Err(e) => {
+                log::warn!("unable to load menu music, using gameplay music: {}", e);
+                None
+            }
+        };
         // load game sound effects
         let game_over = audio::Source::new(ctx, "/game_over.ogg")?;
-        Ok(Assets { music_1, game_over })
+        let level_up = audio::Source::new(ctx, "/level_up.wav")?;
+        let lock = match audio::Source::new(ctx, "/lock.wav") {
+            Ok(lock) => Some(lock),
+            Err(e) => {
+                log::warn!("unable to load lock sound: {}", e);
+                None
+            }
+        };
+        let danger = match audio::Source::new(ctx, "/danger.wav") {
+            Ok(mut danger) => {
+                danger.set_repeat(true);
+                Some(danger)
+            }
+            Err(e) => {
+                log::warn!("unable to load danger sound: {}", e);
+                None
+            }
+        };
+        let tick = match audio::Source::new(ctx, "/tick.wav") {
+            Ok(tick) => Some(tick),
+            Err(e) => {
+                log::warn!("unable to load metronome tick sound: {}", e);
+                None
+            }
+        };
+        let high_score = match audio::Source::new(ctx, "/high_score.wav") {
+            Ok(high_score) => Some(high_score),
+            Err(e) => {
+                log::warn!("unable to load high score sound: {}", e);
+                None
+            }
+        };
+        let resets_exhausted = match audio::Source::new(ctx, "/resets_exhausted.wav") {
+            Ok(resets_exhausted) => Some(resets_exhausted),
+            Err(e) => {
+                log::warn!("unable to load resets exhausted sound: {}", e);
+                None
+            }
+        };
+        // loaded once, it's scaled to the window each time it's drawn
+        let background = match graphics::Image::from_path(ctx, "/background.png") {
+            Ok(background) => Some(background),
+            Err(e) => {
+                log::warn!("unable to load background image: {}", e);
+                None
+            }
+        };
+        Ok(Assets {
+            music_1,
+            menu_music,
+            game_over,
+            level_up,
+            lock,
+            danger,
+            tick,
+            high_score,
+            resets_exhausted,
+            background,
+        })
+    }
+
+    // sets the music volumes for a mix between the menu music (0.0)
+    // and the gameplay music (1.0)
+    fn set_music_mix(&mut self, volume: f32, mix: f32) {
+        match self.menu_music.as_mut() {
+            Some(menu_music) => {
+                self.music_1.set_volume(volume * mix);
+                menu_music.set_volume(volume * (1.0 - mix));
+            }
+            None => self.music_1.set_volume(volume),
+        }
+    }
+
+    fn play_sound_effect(&mut self, ctx: &mut Context, sound_effect: SoundEffect) -> GameResult {
+        match sound_effect {
+            SoundEffect::LevelUp => self.level_up.play(ctx),
+            SoundEffect::Lock { pitch } => match self.lock.as_mut() {
+                Some(lock) => {
+                    lock.set_pitch(pitch);
+                    lock.play(ctx)
+                }
+                None => Ok(()),
+            },
+            SoundEffect::Tick => match self.tick.as_mut() {
+                Some(tick) => tick.play(ctx),
+                None => Ok(()),
+            },
+            SoundEffect::ResetsExhausted => match self.resets_exhausted.as_mut() {
+                Some(resets_exhausted) => resets_exhausted.play(ctx),
+                None => Ok(()),
+            },
+        }
     }
 }
 
@@ -73,17 +391,55 @@ pub struct BlocksState {
     pub state: GameState,
     pub level: usize,
     pub score: usize,
-    pub assets: Assets,
+    pub assets: Option<Assets>, // None without a window, when the game logic runs on its own
     pub controls: GameControls,
+    pub settings: Settings,
     menu_state: menus::MenuState,
     paused_state: menus::PausedState,
+    game_over_menu: menus::GameOverState,
+    confirm_quit: Option<menus::ConfirmQuitState>, // asking before quitting from the menu
+    scenario_menu: Option<menus::ScenarioState>,   // picking a scenario to practice from
+    options_state: menus::OptionsState,
     view_settings: draw::ViewSettings,
     rustomino_bag: RustominoBag,
     gravity_delay: f64, // time between gravity ticks
     total_lines_cleared: usize,
-    hold_used: bool, // if user has held a rustomino, resets on lock
+    hold_used: bool,              // if user has held a rustomino, resets on lock
+    holds_remaining: Option<u32>, // holds left under the hold limit, None when there's no limit
     lockdown_resets: u32,
+    ground_time: f64, // total time the active block has spent on the ground
+    spawn_delay: f64, // time left before gravity applies to a newly spawned block
+    pending_rotation: Option<(Control, f64)>, // a blocked rotation and the time left to retry it
     music_volume: f32,
+    played_music_volume: f32, // glides towards music_volume so changes don't click
+    music_mix: f32,           // 0.0 is the menu music, 1.0 is the gameplay music
+    sound_effects: Vec<SoundEffect>, // sound effects waiting to be played
+    popups: Vec<Popup>,
+    countdown: Option<f64>, // time elapsed in the new game countdown
+    game_over_time: f64,    // time elapsed since the game ended
+    game_over_reason: Option<GameOverReason>,
+    profile: String,          // the player whose settings and scores are in use
+    scoring: ScoringConfig,   // points for clears and drops, from the scoring setting
+    high_score: usize,        // best marathon score so far
+    new_high_score: bool,     // the game that just ended beat the previous high score
+    records: Records,         // best marathon clears of all time
+    session_records: Records, // best marathon clears since the game was started
+    records_changed: bool,    // the all time records need saving
+    combo: usize,             // blocks in a row that have cleared lines
+    back_to_back: usize,      // tetrises and spins in a row without an easier clear
+    mode: GameMode,
+    animations: draw::Animations,
+    demo: Option<DemoPlayer>, // plays the game automatically until a player takes over
+    play_time: f64,           // time spent playing the current game
+    lock_counts: LockCounts,  // where blocks have been locked this game, for the heatmap
+    garbage_schedule: Option<GarbageSchedule>,
+    debug_stepping: DebugStepping,
+    frame_capture: FrameCapture,
+    move_history: MoveHistory,
+    show_active_position: bool, // F5 shows where the active block is in debug builds
+    show_controls_help: bool,   // overlay the control bindings while playing
+    danger: bool,               // the stack is close to the top of the playfield
+    transition: Option<f64>,    // time since the screen started fading in after a change of state
 }
 
 impl BlocksState {
@@ -96,47 +452,172 @@ impl BlocksState {
         // load game resources
         let mut assets = Assets::new(ctx)?;
         assets.music_1.play(ctx)?;
-
-        let control_state = GameControls::default();
-        let playfield = Playfield::new();
+        if let Some(menu_music) = assets.menu_music.as_mut() {
+            menu_music.play(ctx)?;
+        }
+        // the game starts at the menu
+        assets.set_music_mix(MUSIC_VOL, 0.0);
+
+        controls::validate_default_keys();
+        // a block spawning in a wall would break every game, so catch it in development
+        let spawns_valid = playfield::validate_spawn_positions();
+        debug_assert!(spawns_valid, "rustomino spawn positions are out of bounds");
+        // settings, scores and the saved game belong to the profile in use
+        let profile = profile::load_current(ctx.fs.user_data_dir());
+        let profile_dir = profile::dir(ctx.fs.user_data_dir(), &profile);
+        let settings = Settings::load(&profile_dir);
 
         // get the window size
         let (width, height) = ctx.gfx.drawable_size();
 
-        let s = BlocksState {
+        let mut s = BlocksState::without_assets(settings, width, height);
+        s.assets = Some(assets);
+        s.high_score = high_score::load(&profile_dir);
+        s.records = Records::load(&profile_dir);
+        s.garbage_schedule = GarbageSchedule::load(ctx.fs.user_data_dir());
+        // start with a saved game or the mode that was played last highlighted
+        if SavedGame::exists(&profile_dir) {
+            s.menu_state = menus::MenuState::new(true);
+        }
+        s.profile = profile;
+        s.options_state
+            .update(&s.settings, s.music_volume, &s.profile);
+
+        Ok(s)
+    }
+
+    /// the game at the main menu with the provided settings, without loading
+    /// any assets or files from the user's data directory
+    /// this needs no window so the game logic can be run on its own
+    pub fn without_assets(settings: Settings, width: f32, height: f32) -> Self {
+        let mut menu_state = menus::MenuState::new(false);
+        menu_state.select_mode(settings.last_mode);
+        let playfield = Playfield::new();
+
+        BlocksState {
             playfield,
             next_rustomino: None,
             held_rustomino: None,
             previous_state: GameState::Menu,
             state: GameState::Menu, // Start the game at the menu screen
             level: STARTING_LEVEL,
-            assets,
-            controls: control_state,
-            menu_state: menus::MenuState::new(),
+            assets: None,
+            controls: GameControls::with_preset(KeyPreset::from_settings(&settings)),
+            menu_state,
             paused_state: menus::PausedState::new(),
-            view_settings: draw::ViewSettings::new(width, height),
+            game_over_menu: menus::GameOverState::new(false),
+            confirm_quit: None,
+            scenario_menu: None,
+            options_state: menus::OptionsState::new(&settings, MUSIC_VOL, profile::DEFAULT_PROFILE),
+            view_settings: draw::ViewSettings::new(
+                width,
+                height,
+                settings.show_buffer_rows,
+                settings.hide_staging,
+                settings.theme.theme(),
+                settings.hud_scale.scale(),
+                settings.colored_ghost,
+                settings.mono_blocks,
+                settings.show_frame,
+            ),
             score: 0,
             rustomino_bag: RustominoBag::new(),
-            gravity_delay: gravity_delay(STARTING_LEVEL),
+            gravity_delay: gravity_delay(STARTING_LEVEL, settings.gravity_curve),
             total_lines_cleared: 0,
             hold_used: false,
+            holds_remaining: None,
             lockdown_resets: 0,
+            ground_time: 0.0,
+            spawn_delay: 0.0,
+            pending_rotation: None,
             music_volume: MUSIC_VOL,
-        };
-
-        Ok(s)
+            played_music_volume: MUSIC_VOL,
+            music_mix: 0.0,
+            sound_effects: Vec::new(),
+            popups: Vec::new(),
+            countdown: None,
+            game_over_time: 0.0,
+            game_over_reason: None,
+            scoring: ScoringConfig::for_scoring(settings.scoring),
+            high_score: 0,
+            profile: profile::DEFAULT_PROFILE.to_string(),
+            new_high_score: false,
+            records: Records::default(),
+            session_records: Records::default(),
+            records_changed: false,
+            combo: 0,
+            back_to_back: 0,
+            mode: GameMode::Marathon,
+            animations: draw::Animations::finished(),
+            demo: None,
+            play_time: 0.0,
+            lock_counts: [[0; PLAYFIELD_SLOTS[0]]; PLAYFIELD_SLOTS[1]],
+            garbage_schedule: None,
+            debug_stepping: DebugStepping::default(),
+            frame_capture: FrameCapture::default(),
+            move_history: MoveHistory::default(),
+            show_active_position: false,
+            show_controls_help: false,
+            danger: false,
+            transition: None,
+            settings,
+        }
     }
 
     fn playing_update(&mut self, delta_time: f64) {
+        self.retry_pending_rotation(delta_time);
+
         let Some(current_state) = self.playfield.get_active_state() else {
             return;
         };
+
+        // a new block waits out the spawn delay before gravity applies
+        // unless the player is already soft dropping it
+        if self.spawn_delay > 0.0 && !self.controls.is_down(Control::SoftDrop) {
+            self.spawn_delay -= delta_time;
+            return;
+        }
+        self.spawn_delay = 0.0;
+
+        // cap the total time a block can stay on the ground
+        // regardless of how many times the lockdown timer has been reset
+        if !self.playfield.active_can_fall() {
+            self.ground_time += delta_time;
+            if self.settings.max_ground_time > 0.0
+                && self.ground_time >= self.settings.max_ground_time
+            {
+                log::info!("maximum ground time exceeded");
+                self.lock();
+                return;
+            }
+        }
+
+        let soft_dropping = self.controls.is_down(Control::SoftDrop);
+        // holding soft drop speeds up gravity instead of repeating the soft drop
+        let gravity_delay = match self.settings.soft_drop_speed.factor() {
+            Some(factor) if soft_dropping => self.gravity_delay / factor,
+            None if soft_dropping => 0.0,
+            _ => self.gravity_delay,
+        };
+
         match current_state {
-            RustominoState::Falling { time } if time + delta_time >= self.gravity_delay => {
+            RustominoState::Falling { time } if time + delta_time >= gravity_delay => {
                 // check to see if the block can still fall
                 if self.playfield.active_can_fall() {
+                    let distance = self.playfield.active_drop_distance();
                     // apply gravity if it can
                     self.playfield.apply_gravity();
+                    // an instant soft drop falls all the way in a single tick
+                    if soft_dropping && self.settings.soft_drop_speed == SoftDropSpeed::Instant {
+                        while self.playfield.active_can_fall() {
+                            self.playfield.apply_gravity();
+                        }
+                    }
+                    // rows fallen while soft drop is held count as soft dropped
+                    if soft_dropping {
+                        let rows = distance - self.playfield.active_drop_distance();
+                        self.score_drop(rows, self.scoring.soft_drop_row);
+                    }
                     // reset the accumulated time
                     self.playfield
                         .set_active_state(RustominoState::Falling { time: 0. });
@@ -151,7 +632,7 @@ impl BlocksState {
                 });
             }
             RustominoState::Lockdown { time }
-                if self.lockdown_resets >= LOCKDOWN_MAX_RESETS
+                if self.lockdown_resets >= self.settings.lockdown_max_resets
                     && !self.playfield.active_can_fall() =>
             {
                 // accumulate lockdown time
@@ -162,10 +643,15 @@ impl BlocksState {
                 // if the user has exceeded the maximum number of resets
                 // lock the block
                 log::info!("maximum lockdown resets exceeded");
+                // let the player know stalling is over and the lock was forced
+                if self.settings.resets_exhausted_sound {
+                    self.sound_effects.push(SoundEffect::ResetsExhausted);
+                }
                 self.lock();
             }
             RustominoState::Lockdown { time }
-                if time + delta_time >= LOCKDOWN_DELAY && !self.playfield.active_can_fall() =>
+                if time + delta_time >= self.settings.lockdown_delay
+                    && !self.playfield.active_can_fall() =>
             {
                 // if the current lockdown time has exceed the maximum
                 // lock the block
@@ -188,6 +674,17 @@ impl BlocksState {
             self.lockdown_resets += 1;
             log::debug!("incrementing lockdown resets: {}", self.lockdown_resets);
         }
+        self.start_lockdown();
+    }
+
+    // starts the lockdown timer without counting a lockdown reset
+    // or locks the block straight away when locking on contact
+    fn start_lockdown(&mut self) {
+        if self.settings.lock_on_contact {
+            log::info!("locking on contact");
+            self.lock();
+            return;
+        }
         log::info!("setting active rustomino state to lockdown");
         self.playfield
             .set_active_state(RustominoState::Lockdown { time: 0. });
@@ -202,9 +699,19 @@ impl BlocksState {
             None => self.rustomino_bag.get_next(),
         };
         self.next_rustomino = Some(self.rustomino_bag.get_next());
+        self.animations.next = 0.0;
         next_rustomino
     }
 
+    // turn a rustomino to the orientation it should spawn in
+    fn spawn_orientation(&self, rustomino: Rustomino) -> Rustomino {
+        if self.settings.spawn_flat_up {
+            rustomino.flat_side_up()
+        } else {
+            rustomino
+        }
+    }
+
     fn ready_playfield(&mut self) -> bool {
         // check to see if the playfield is ready for the next rustomino
         if !self.playfield.ready_for_next() {
@@ -215,63 +722,172 @@ impl BlocksState {
 
         // get the next rustomino
         let active_rustomino = self.get_next_rustomino();
+        self.animations.spawn = 0.0;
 
         // add the next rustomino to the playfield
-        if !self.playfield.set_active(active_rustomino) {
+        if !self
+            .playfield
+            .set_active(self.spawn_orientation(active_rustomino))
+        {
             log::info!("couldn't add next piece to board, collided with locked block");
             // game over if it can't be placed without a collision
-            self.game_over();
+            self.game_over(GameOverReason::BlockOut);
             return false;
         }
 
+        // give the player a moment to see the new block before it falls
+        self.spawn_delay = self.settings.spawn_delay;
+
+        // per block DAS makes held directions charge up again for each new block
+        if !self.settings.das_carry {
+            self.controls.reset_charge();
+        }
+
         true
     }
 
     fn translate(&mut self, direction: TranslationDirection) {
         log::info!("translate called, direction: {:?}", direction);
+        let action = format!("{:?}", direction);
         if self.playfield.translate_active(direction) {
             self.increment_lockdown_resets();
+            self.record_move(action);
+        } else {
+            self.record_move(format!("{} blocked", action));
         }
         log::trace!("playfield:\n{}", self.playfield);
     }
 
     fn rotate(&mut self, rotation: Rotation) {
         log::info!("rotate called, direction: {:?}", rotation);
-        if self.playfield.rotate_active(rotation) {
+        let control = match rotation {
+            Rotation::Cw => Control::RotateCW,
+            Rotation::Ccw => Control::RotateCCW,
+        };
+        self.rotate_or_buffer(control);
+        log::trace!("playfield:\n{}", self.playfield);
+    }
+
+    // attempts the rotation for a rotate control, returns true if the block rotated
+    fn try_rotation(&mut self, control: Control) -> bool {
+        let rotated = match control {
+            Control::RotateCW => self.playfield.rotate_active(Rotation::Cw),
+            Control::RotateCCW => self.playfield.rotate_active(Rotation::Ccw),
+            Control::Rotate180 => self.playfield.rotate_active_180(),
+            _ => false,
+        };
+        if rotated {
             self.increment_lockdown_resets();
         }
-        log::trace!("playfield:\n{}", self.playfield);
+        rotated
+    }
+
+    // keep a move for the move history overlay
+    fn record_move(&mut self, action: String) {
+        self.move_history.record(self.play_time, action);
+    }
+
+    // with sticky rotation a blocked rotation is kept and retried for a moment
+    // so it still happens if the block moves somewhere it fits
+    fn rotate_or_buffer(&mut self, control: Control) {
+        if self.try_rotation(control) {
+            self.pending_rotation = None;
+            self.record_move(format!("{:?}", control));
+        } else if self.settings.sticky_rotation {
+            self.record_move(format!("{:?} buffered", control));
+            log::debug!(
+                "{:?} blocked, retrying for {}s",
+                control,
+                STICKY_ROTATION_TIME
+            );
+            self.pending_rotation = Some((control, STICKY_ROTATION_TIME));
+        } else {
+            self.record_move(format!("{:?} blocked", control));
+        }
+    }
+
+    fn retry_pending_rotation(&mut self, delta_time: f64) {
+        let Some((control, time)) = self.pending_rotation.take() else {
+            return;
+        };
+        if self.try_rotation(control) {
+            log::info!("pending {:?} applied", control);
+            self.record_move(format!("{:?} from buffer", control));
+        } else if time > delta_time {
+            self.pending_rotation = Some((control, time - delta_time));
+        }
     }
 
     // performs a soft drop
     fn soft_drop(&mut self) {
         log::debug!("soft drop called");
+        self.record_move("SoftDrop".to_string());
+        let distance = self.playfield.active_drop_distance();
+        // the faster gravity in playing_update takes over while soft drop is held
+        if self.settings.soft_drop_speed == SoftDropSpeed::Instant {
+            while self.playfield.translate_active(TranslationDirection::Down) {}
+        }
         // attempt to translate the block down
-        if !self.playfield.translate_active(TranslationDirection::Down) {
-            // per the teris guide we shouldn't lock a block with soft drop
+        let moved = self.playfield.translate_active(TranslationDirection::Down);
+        let rows = distance - self.playfield.active_drop_distance();
+        self.score_drop(rows, self.scoring.soft_drop_row);
+        if !moved {
+            // per the teris guide we shouldn't lock a block with soft drop,
+            // touching down only starts the lockdown timer. soft dropping isn't
+            // a move so it never uses up a lockdown reset, whether this is the
+            // block's first contact or it's being pushed down again
             let Some(state) = self.playfield.get_active_state() else {
                 return;
             };
-            // check if the block state is already in lockdown
             if !variants_equal(&state, &RustominoState::Lockdown { time: 0.0 }) {
-                self.set_lockdown();
+                self.start_lockdown();
             }
-            // else do nothing
+            // else the block is already in lockdown and the timer keeps running
         }
         log::trace!("playfield:\n{}", self.playfield);
     }
 
+    // drops score by the row and aren't multiplied by the level
+    fn score_drop(&mut self, rows: usize, row_score: usize) {
+        self.score += rows * row_score;
+    }
+
     fn hard_drop(&mut self) {
+        if !self.settings.hard_drop_lock {
+            self.drop_without_lock();
+            return;
+        }
+        let rows = self.playfield.active_drop_distance();
         self.playfield.hard_drop_active();
+        self.score_drop(rows, self.scoring.hard_drop_row);
         log::info!("hard drop");
+        self.record_move("HardDrop".to_string());
         self.lock();
         log::trace!("playfield:\n{}", self.playfield);
     }
 
+    // moves the block to the bottom and lets the lockdown delay lock it
+    fn drop_without_lock(&mut self) {
+        log::info!("hard drop without locking");
+        self.record_move("HardDrop without lock".to_string());
+        let rows = self.playfield.active_drop_distance();
+        while self.playfield.translate_active(TranslationDirection::Down) {}
+        self.score_drop(rows, self.scoring.hard_drop_row);
+        if let Some(RustominoState::Falling { time: _ }) = self.playfield.get_active_state() {
+            self.set_lockdown();
+        }
+        log::trace!("playfield:\n{}", self.playfield);
+    }
+
     fn hold(&mut self) {
         // check to see if the player has used the hold action
         // and they haven't yet locked the previous block they took from hold
-        if self.hold_used {
+        if !self.hold_available() {
+            return;
+        }
+        // there's nothing to hold between locking a block and the next one spawning
+        if self.playfield.active_rustomino.is_none() {
+            log::debug!("no active rustomino to hold");
             return;
         }
 
@@ -281,20 +897,57 @@ impl BlocksState {
             None => self.get_next_rustomino(), // use the next rustomino
         };
 
+        self.record_move("Hold".to_string());
         // take active_rustomino and make it the hold_rustomino
         self.held_rustomino = self.playfield.take_active();
+        self.pending_rotation = None;
+        self.animations.hold = 0.0;
+        // the swapped in rustomino starts fresh
+        // so a hard drop straight after the hold locks it correctly
+        self.ground_time = 0.0;
+        self.lockdown_resets = 0;
 
         // trigger game over in the unusual circumstance
         // a collision with a locked block occurs
         // when the next rustomino is added to the board
-        if !self.playfield.set_active(next_rustomino.reset()) {
+        if !self
+            .playfield
+            .set_active(self.spawn_orientation(next_rustomino.reset()))
+        {
             log::info!("couldn't add held piece to board, collided with lock block");
-            self.game_over();
+            self.game_over(GameOverReason::BlockOut);
         }
 
         // prevent the player from taking the hold action again
         // until the next rustomino is locked
         self.hold_used = true;
+        if let Some(holds) = self.holds_remaining.as_mut() {
+            *holds -= 1;
+            log::debug!("holds remaining: {}", holds);
+        }
+    }
+
+    // refill the holds at the start of a game
+    // and at each new level when the limit is per level
+    fn reset_hold_limit(&mut self) {
+        self.holds_remaining = match self.settings.hold_limit {
+            0 => None,
+            limit => Some(limit),
+        };
+    }
+
+    // hold can only be used once per block unless infinite hold is enabled
+    fn hold_available(&self) -> bool {
+        (self.settings.infinite_hold || !self.hold_used) && self.holds_remaining != Some(0)
+    }
+
+    // the hold, preview and spawn animations are skipped when reducing motion
+    fn animations(&self) -> draw::Animations {
+        if self.settings.reduce_motion {
+            draw::Animations::finished()
+        } else {
+            self.animations
+        }
     }
 
     fn pause(&mut self) {
@@ -308,8 +961,16 @@ impl BlocksState {
         self.set_state(GameState::Playing);
     }
 
-    fn game_over(&mut self) {
-        log::info!("Game Over! Score: {}", self.score);
+    fn game_over(&mut self, reason: GameOverReason) {
+        log::info!("Game Over! Score: {} Reason: {:?}", self.score, reason);
+        self.game_over_reason = Some(reason);
+        self.new_high_score = self.mode.counts_for_records() && self.score > self.high_score;
+        if self.new_high_score {
+            log::info!("new high score! previous best: {}", self.high_score);
+            self.high_score = self.score;
+        }
+        // only marathon games are dealt from a seed that starting a game can reuse
+        self.game_over_menu = menus::GameOverState::new(self.mode == GameMode::Marathon);
         self.controls.clear_inputs();
         self.set_state(GameState::GameOver);
     }
@@ -318,22 +979,130 @@ impl BlocksState {
         self.playfield = Playfield::new();
         self.next_rustomino = None;
         self.held_rustomino = None;
+        self.start_transition(self.state, GameState::Menu);
         self.state = GameState::Menu; // Start the game at the menu screen
         self.previous_state = GameState::Menu;
         self.level = STARTING_LEVEL;
         self.score = 0;
         self.rustomino_bag = RustominoBag::new();
-        self.gravity_delay = gravity_delay(STARTING_LEVEL);
+        self.gravity_delay = gravity_delay(STARTING_LEVEL, self.settings.gravity_curve);
         self.total_lines_cleared = 0;
         self.hold_used = false;
+        self.holds_remaining = None;
         self.lockdown_resets = 0;
+        self.ground_time = 0.0;
+        self.spawn_delay = 0.0;
+        self.pending_rotation = None;
+        self.popups.clear();
+        self.countdown = None;
+        self.game_over_reason = None;
+        self.new_high_score = false;
+        self.combo = 0;
+        self.back_to_back = 0;
+        self.mode = GameMode::Marathon;
+        self.animations = draw::Animations::finished();
+        self.demo = None;
+        self.play_time = 0.0;
+        self.lock_counts = [[0; PLAYFIELD_SLOTS[0]]; PLAYFIELD_SLOTS[1]];
+        if let Some(schedule) = self.garbage_schedule.as_mut() {
+            schedule.reset();
+        }
+    }
+
+    // add any garbage that's due by the play clock
+    fn add_scheduled_garbage(&mut self) {
+        let Some(schedule) = self.garbage_schedule.as_mut() else {
+            return;
+        };
+        let rows = schedule.due(self.play_time);
+        if rows == 0 {
+            return;
+        }
+        let hole = ::rand::random::<usize>() % PLAYFIELD_SLOTS[0];
+        if !self.playfield.add_garbage(rows, hole) {
+            log::info!("garbage pushed blocks out of the playfield");
+            self.game_over(GameOverReason::TopOut);
+        }
+    }
+
+    // the trainer feeds a single repeating block type for practice
+    // reuse a seed to replay the same block sequence as an earlier game
+    fn new_rustomino_bag(&self, seed: Option<u64>) -> RustominoBag {
+        match (self.settings.trainer_block, seed) {
+            (Some(rtype), _) => RustominoBag::scripted(vec![rtype]),
+            (None, Some(seed)) => RustominoBag::with_seed(seed),
+            (None, None) => RustominoBag::new(),
+        }
+    }
+
+    fn start_demo(&mut self) {
+        log::info!("starting demo");
+        self.demo = Some(DemoPlayer::new());
+        self.mode = GameMode::Demo;
+        self.resume();
+    }
+
+    // hand the game over from the demo player to a human
+    fn end_demo(&mut self) {
+        log::info!("player took over from the demo");
+        self.demo = None;
+        self.controls.clear_inputs();
+    }
+
+    fn start_countdown(&mut self) {
+        log::info!("starting new game countdown");
+        self.countdown = Some(0.0);
+    }
+
+    fn update_countdown(&mut self, delta_time: f64) {
+        let Some(time) = self.countdown else {
+            return;
+        };
+        self.countdown = if time + delta_time >= COUNTDOWN_READY_TIME + COUNTDOWN_GO_TIME {
+            log::info!("new game countdown finished");
+            None
+        } else {
+            Some(time + delta_time)
+        };
+    }
+
+    // the countdown text to show the player, if the countdown is running
+    fn countdown_text(&self) -> Option<&'static str> {
+        match self.countdown {
+            Some(time) if time < COUNTDOWN_READY_TIME => Some("Ready?"),
+            Some(_) => Some("Go!"),
+            None => None,
+        }
+    }
+
+    // the level gravity is based on, held at the speed cap
+    // so scoring and line tracking can carry on past it
+    fn gravity_level(&self) -> usize {
+        match self.settings.speed_cap {
+            0 => self.level,
+            cap => self.level.min(cap),
+        }
     }
 
     fn increase_game_level(&mut self) {
         self.level += 1;
         log::info!("increasing game level to {}", self.level);
         // get the gravity tick delay for the next level
-        self.gravity_delay = gravity_delay(self.level);
+        self.gravity_delay = gravity_delay(self.gravity_level(), self.settings.gravity_curve);
+        if self.settings.hold_limit_scope == HoldLimitScope::Level {
+            self.reset_hold_limit();
+        }
+        // let the player know the game has sped up
+        self.sound_effects.push(SoundEffect::LevelUp);
+        self.popups
+            .push(Popup::new(format!("Level {}", self.level)));
+    }
+
+    fn update_popups(&mut self, delta_time: f64) {
+        for popup in self.popups.iter_mut() {
+            popup.update(delta_time);
+        }
+        self.popups.retain(|popup| !popup.expired());
     }
 
     fn lock(&mut self) {
@@ -343,26 +1112,76 @@ impl BlocksState {
         };
 
         log::info!("locking block type: {:?}", rustomino.rtype);
+        self.move_history.record(
+            self.play_time,
+            format!(
+                "Lock {:?} after {} resets",
+                rustomino.rtype, self.lockdown_resets
+            ),
+        );
         log::debug!("blocks: {:?}", rustomino.playfield_slots());
 
-        // if the block we've been asked to lock is fully
-        // out of bounds the game is over
-        if fully_out_of_bounds(&rustomino.playfield_slots()) {
-            log::info!("block we are locking is fully out of playfield");
-            self.game_over();
-            return;
+        // lines cleared below a block that locks above the playfield
+        // can bring it back down, so lock out is checked after clearing
+        let locked_slots = rustomino.playfield_slots();
+
+        // the lock sound gets higher as the stack grows
+        // so rapid stacking doesn't sound the same every time
+        let pitch = if self.settings.lock_pitch_variation {
+            let top = rustomino
+                .playfield_slots()
+                .iter()
+                .map(|b| b[1])
+                .max()
+                .unwrap_or(0);
+            1.0 + LOCK_PITCH_RANGE * top.max(0) as f32 / PLAYFIELD_SLOTS[1] as f32
+        } else {
+            1.0
+        };
+        self.sound_effects.push(SoundEffect::Lock { pitch });
+
+        // blocks above the playfield aren't counted in the heatmap
+        for slot in rustomino.playfield_slots() {
+            if let Some(count) = self
+                .lock_counts
+                .get_mut(slot.y as usize)
+                .and_then(|row| row.get_mut(slot.x as usize))
+            {
+                *count += 1;
+            }
         }
 
+        // check for a t-spin before the block is locked
+        let t_spin = self.playfield.active_t_spin();
+        // with all spin the other blocks can spin too
+        let spin = (self.settings.all_spin
+            && rustomino.rtype != RustominoType::T
+            && self.playfield.active_immobile_spin())
+        .then_some(rustomino.rtype);
+
         self.hold_used = false;
+        self.pending_rotation = None;
         self.playfield.lock_active();
 
         self.lockdown_resets = 0;
-        self.handle_completed_lines();
+        self.ground_time = 0.0;
+        let cleared_lines = self.playfield.get_complete_lines();
+        self.handle_completed_lines(t_spin, spin);
+
+        // if the block is still fully out of bounds the game is over
+        if locked_above_playfield(&locked_slots, &cleared_lines) {
+            log::info!("locked block is fully out of playfield");
+            self.game_over(GameOverReason::LockOut);
+        }
     }
 
     // increment the number of lockdown resets
     // and reset the lockdown time to 0
     fn increment_lockdown_resets(&mut self) {
+        if self.settings.lockdown_reset == LockdownReset::Step {
+            self.step_reset_lockdown();
+            return;
+        }
         let Some(active_state) = self.playfield.get_active_state() else {
             return;
         };
@@ -397,16 +1216,64 @@ impl BlocksState {
         }
     }
 
-    fn handle_completed_lines(&mut self) {
-        let cleared_lines = self.playfield.clear_completed_lines();
-        if cleared_lines.is_empty() {
+    // with step reset moving or rotating never resets the lockdown timer
+    // it only restarts once the block has fallen down a row
+    fn step_reset_lockdown(&mut self) {
+        let Some(RustominoState::Lockdown { time: _ }) = self.playfield.get_active_state() else {
             return;
+        };
+        // if the block can fall again it needs to continue falling
+        if self.playfield.active_can_fall() {
+            log::debug!("block can fall setting rustomino state back to falling");
+            self.playfield
+                .set_active_state(RustominoState::Falling { time: 0. });
         }
+    }
 
+    fn handle_completed_lines(&mut self, t_spin: TSpin, spin: Option<RustominoType>) {
+        // classic scoring doesn't recognise t-spins
+        let t_spin = if self.scoring.spins {
+            t_spin
+        } else {
+            TSpin::None
+        };
+        let cleared_lines = self.playfield.clear_completed_lines();
         let num_lines_cleared = cleared_lines.len();
 
+        // t-spins score even when they don't clear any lines
+        if t_spin != TSpin::None {
+            log::info!("{:?} t-spin, lines cleared: {}", t_spin, num_lines_cleared);
+            self.popups
+                .push(Popup::new(t_spin_name(t_spin, num_lines_cleared)));
+        }
+
+        // other blocks only spin when they clear lines and score like a mini t-spin
+        let spin = spin.filter(|_| num_lines_cleared > 0 && self.scoring.spins);
+        let t_spin = match spin {
+            Some(rtype) => {
+                log::info!("{:?} spin, lines cleared: {}", rtype, num_lines_cleared);
+                self.popups
+                    .push(Popup::new(spin_name(rtype, num_lines_cleared)));
+                TSpin::Mini
+            }
+            None => t_spin,
+        };
+
+        self.update_records(num_lines_cleared, t_spin);
+
+        if num_lines_cleared == 0 && t_spin == TSpin::None {
+            return;
+        }
+
         // score the completed lines and append it to the total score
-        let score = score_cleared_lines(num_lines_cleared, self.level);
+        let score = score_cleared_lines(
+            &self.scoring,
+            num_lines_cleared,
+            t_spin,
+            self.level,
+            self.combo,
+            self.back_to_back,
+        );
         self.score += score;
         log::info!(
             "scored! game_level: {} score: {} lines cleared: {}",
@@ -415,6 +1282,10 @@ impl BlocksState {
             num_lines_cleared
         );
 
+        if num_lines_cleared == 0 {
+            return;
+        }
+
         // track the total number of lines cleared
         self.total_lines_cleared += num_lines_cleared;
         log::info!(
@@ -423,10 +1294,126 @@ impl BlocksState {
         );
 
         // increase the game level every LINES_PER_LEVEL
-        if self.total_lines_cleared >= (self.level + 1) * LINES_PER_LEVEL {
+        if self.total_lines_cleared >= next_level_lines(self.level) {
             self.increase_game_level();
         }
     }
+
+    // how long the active block has left before it locks, for the hud
+    fn lock_timer(&self) -> Option<draw::LockTimer> {
+        if !self.settings.show_lock_timer {
+            return None;
+        }
+        let remaining = match self.playfield.get_active_state()? {
+            RustominoState::Lockdown { time } if self.settings.lockdown_delay > 0.0 => {
+                1.0 - time / self.settings.lockdown_delay
+            }
+            RustominoState::Lockdown { time: _ } => 0.0,
+            RustominoState::Falling { time: _ } => 1.0,
+        };
+        Some(draw::LockTimer {
+            remaining: remaining.clamp(0.0, 1.0) as f32,
+            resets_used: self.lockdown_resets,
+            max_resets: self.settings.lockdown_max_resets,
+        })
+    }
+
+    // a block that doesn't clear lines ends the combo but not the back-to-back chain
+    fn update_records(&mut self, num_lines_cleared: usize, t_spin: TSpin) {
+        if num_lines_cleared == 0 {
+            self.combo = 0;
+            return;
+        }
+        self.combo += 1;
+        if num_lines_cleared >= 4 || t_spin != TSpin::None {
+            self.back_to_back += 1;
+        } else {
+            self.back_to_back = 0;
+        }
+
+        if !self.mode.counts_for_records() {
+            return;
+        }
+        let records = Records {
+            longest_combo: self.combo,
+            best_back_to_back: self.back_to_back,
+            most_lines: num_lines_cleared,
+        };
+        self.session_records.improve(&records);
+        if self.records.improve(&records) {
+            log::info!("new records: {:?}", self.records);
+            self.records_changed = true;
+        }
+    }
+
+    // the folder the current profile's files are kept in
+    fn profile_dir(&self, ctx: &Context) -> PathBuf {
+        profile::dir(ctx.fs.user_data_dir(), &self.profile)
+    }
+
+    // switch to the next or previous player's settings, scores and saved game
+    // only from the main menu so a game in progress stays with its player
+    fn change_profile(&mut self, ctx: &Context, forward: bool) {
+        if self.previous_state != GameState::Menu {
+            log::info!("profiles can only be changed from the main menu");
+            return;
+        }
+        let profile = profile::cycle(ctx.fs.user_data_dir(), &self.profile, forward);
+        if profile == self.profile {
+            return;
+        }
+        // anything changed so far belongs to the profile being left
+        self.settings.save(&self.profile_dir(ctx));
+        self.save_records(ctx);
+
+        self.profile = profile;
+        log::info!("profile set to {}", self.profile);
+        profile::save_current(ctx.fs.user_data_dir(), &self.profile);
+        let dir = self.profile_dir(ctx);
+        self.settings = Settings::load(&dir);
+        self.scoring = ScoringConfig::for_scoring(self.settings.scoring);
+        self.high_score = high_score::load(&dir);
+        self.records = Records::load(&dir);
+        self.session_records = Records::default();
+        self.controls = GameControls::with_preset(KeyPreset::from_settings(&self.settings));
+        self.rebuild_view_settings();
+        let can_continue = SavedGame::exists(&dir);
+        self.menu_state = menus::MenuState::new(can_continue);
+        if !can_continue {
+            self.menu_state.select_mode(self.settings.last_mode);
+        }
+        self.options_state
+            .update(&self.settings, self.music_volume, &self.profile);
+    }
+
+    // write the all time records once a game has beaten them
+    fn save_records(&mut self, ctx: &Context) {
+        if self.records_changed {
+            self.records.save(&self.profile_dir(ctx));
+            self.records_changed = false;
+        }
+    }
+
+    fn reset_records(&mut self, ctx: &Context) {
+        log::info!("resetting records");
+        self.records = Records::default();
+        self.session_records = Records::default();
+        self.records.save(&self.profile_dir(ctx));
+        self.records_changed = false;
+    }
+
+    /// returns the lines cleared toward the next level
+    /// and the total lines needed to reach it from the current level
+    pub fn level_progress(&self) -> (usize, usize) {
+        let next = next_level_lines(self.level);
+        let current = next_level_lines(self.level - 1);
+        (
+            self.total_lines_cleared
+                .saturating_sub(current)
+                .min(next - current),
+            next - current,
+        )
+    }
     fn translate_left(&mut self) {
         self.translate(TranslationDirection::Left);
     }
@@ -439,7 +1426,12 @@ impl BlocksState {
     fn rotate_ccw(&mut self) {
         self.rotate(Rotation::Ccw);
     }
-    // returns a closure which handles the provided
+    fn rotate_180(&mut self) {
+        log::info!("rotate 180 called");
+        self.rotate_or_buffer(Control::Rotate180);
+        log::trace!("playfield:\n{}", self.playfield);
+    }
+    // returns a closure which handles the provided
     // control for the game
     pub fn control_handler(&mut self, control: Control) -> fn(&mut BlocksState) {
         match control {
@@ -447,85 +1439,854 @@ impl BlocksState {
             Control::Right => BlocksState::translate_right,
             Control::RotateCW => BlocksState::rotate_cw,
             Control::RotateCCW => BlocksState::rotate_ccw,
+            Control::Rotate180 => BlocksState::rotate_180,
             Control::SoftDrop => BlocksState::soft_drop,
             Control::HardDrop => BlocksState::hard_drop,
             Control::Hold => BlocksState::hold,
+            Control::Pause => BlocksState::pause,
         }
     }
 
-    fn menu_item_selected(&mut self) {
-        if self.menu_state.selected() == 0 {
-            self.resume();
-            self.menu_state.reset_selection();
-        } else if self.menu_state.selected() == 1 {
-            self.set_state(GameState::Options);
-        } else if self.menu_state.selected() == 2 {
+    // controls that move the block are ignored between a block locking
+    // and the next one spawning, rather than acting on a block that isn't there
+    fn handle_control(&mut self, control: Control) {
+        if control.moves_block() && self.playfield.active_rustomino.is_none() {
+            log::debug!("ignoring {:?} with no active rustomino", control);
+            return;
+        }
+        self.control_handler(control)(self);
+    }
+
+    fn menu_item_selected(&mut self, ctx: &Context) {
+        match self.menu_state.selected_item() {
+            MenuItem::Continue => self.continue_game(ctx),
+            MenuItem::StartGame => {
+                self.set_last_mode(ctx, LastMode::Marathon);
+                self.start_game(None);
+            }
+            MenuItem::Practice => {
+                let names = Scenario::list(ctx.fs.user_data_dir());
+                self.scenario_menu = Some(menus::ScenarioState::new(names));
+            }
+            MenuItem::WatchDemo => {
+                self.set_last_mode(ctx, LastMode::Demo);
+                self.start_demo();
+            }
+            MenuItem::Options => self.set_state(GameState::Options),
+            MenuItem::Quit => self.quit_from_menu(),
+        }
+    }
+
+    // save the game being left from the pause menu so it can be continued
+    // demos and practice aren't saved, returns true if the game was saved
+    fn save_game(&self, ctx: &Context) -> bool {
+        if matches!(self.mode, GameMode::Demo | GameMode::Practice) {
+            return false;
+        }
+        let slots = self.playfield.slots.map(|row| {
+            row.map(|slot| match slot {
+                SlotState::Locked(_) | SlotState::Garbage => slot,
+                _ => SlotState::Empty,
+            })
+        });
+        let saved_game = SavedGame {
+            mode: self.mode,
+            seed: self.rustomino_bag.seed(),
+            drawn: self.rustomino_bag.drawn(),
+            level: self.level,
+            score: self.score,
+            total_lines_cleared: self.total_lines_cleared,
+            combo: self.combo,
+            back_to_back: self.back_to_back,
+            hold_used: self.hold_used,
+            holds_remaining: self.holds_remaining,
+            lockdown_resets: self.lockdown_resets,
+            ground_time: self.ground_time,
+            play_time: self.play_time,
+            next: self.next_rustomino.as_ref().map(|r| r.rtype),
+            held: self.held_rustomino.as_ref().map(|r| r.rtype),
+            active: self.playfield.active_rustomino.clone(),
+            slots,
+        };
+        saved_game.save(&self.profile_dir(ctx));
+        true
+    }
+
+    // pick up the saved game where it was left
+    // the save is removed once it's loaded so it can only be continued once
+    fn continue_game(&mut self, ctx: &Context) {
+        let dir = &self.profile_dir(ctx);
+        let saved_game = SavedGame::load(dir);
+        SavedGame::delete(dir);
+        self.menu_state = menus::MenuState::new(false);
+        self.menu_state.select_mode(self.settings.last_mode);
+        let Some(saved_game) = saved_game else {
+            log::warn!("unable to continue the saved game");
+            return;
+        };
+
+        self.new_game();
+        self.playfield.slots = saved_game.slots;
+        if let Some(active) = saved_game.active {
+            if !self.playfield.set_active(active) {
+                log::warn!("saved game's active block overlaps the playfield");
+                self.new_game();
+                return;
+            }
+        }
+        self.mode = saved_game.mode;
+        self.rustomino_bag = match saved_game.mode {
+            GameMode::Trainer(rtype) => RustominoBag::scripted(vec![rtype]),
+            _ => RustominoBag::resumed(saved_game.seed, saved_game.drawn),
+        };
+        self.next_rustomino = saved_game.next.map(Rustomino::new);
+        self.held_rustomino = saved_game.held.map(Rustomino::new);
+        self.level = saved_game.level;
+        self.score = saved_game.score;
+        self.gravity_delay = gravity_delay(self.gravity_level(), self.settings.gravity_curve);
+        self.total_lines_cleared = saved_game.total_lines_cleared;
+        self.combo = saved_game.combo;
+        self.back_to_back = saved_game.back_to_back;
+        self.hold_used = saved_game.hold_used;
+        self.holds_remaining = saved_game.holds_remaining;
+        self.lockdown_resets = saved_game.lockdown_resets;
+        self.ground_time = saved_game.ground_time;
+        self.play_time = saved_game.play_time;
+        // skip the garbage that was already added before the game was saved
+        if let Some(schedule) = self.garbage_schedule.as_mut() {
+            schedule.due(self.play_time);
+        }
+        log::info!("continuing saved game at level {}", self.level);
+        self.resume();
+        self.start_countdown();
+    }
+
+    // pick a scenario or go back to the main menu
+    fn scenario_action(&mut self, ctx: &Context, action: MenuAction) {
+        let Some(scenario_menu) = self.scenario_menu.as_mut() else {
+            return;
+        };
+        match action {
+            MenuAction::Up => scenario_menu.previous(),
+            MenuAction::Down => scenario_menu.next(),
+            MenuAction::Select => {
+                let scenario = scenario_menu
+                    .selected_scenario()
+                    .map(|name| Scenario::load(ctx.fs.user_data_dir(), name));
+                match scenario {
+                    Some(Some(scenario)) => {
+                        self.scenario_menu = None;
+                        self.start_scenario(scenario);
+                    }
+                    // stay on the list when the scenario couldn't be loaded
+                    Some(None) => {}
+                    None => self.scenario_menu = None,
+                }
+            }
+            MenuAction::Back => self.scenario_menu = None,
+            _ => {}
+        }
+    }
+
+    // set up the scenario's position and wait on the pause menu
+    // so it can be looked over before playing
+    fn start_scenario(&mut self, scenario: Scenario) {
+        log::info!("starting scenario {}", scenario.name);
+        self.new_game();
+        self.playfield.slots = scenario.slots;
+        if !self
+            .playfield
+            .set_active(self.spawn_orientation(Rustomino::new(scenario.active)))
+        {
+            log::warn!("scenario's active block overlaps the playfield");
+            self.new_game();
+            return;
+        }
+        self.mode = GameMode::Practice;
+        self.rustomino_bag = RustominoBag::queued(scenario.next);
+        self.next_rustomino = Some(self.rustomino_bag.get_next());
+        self.held_rustomino = scenario.held.map(Rustomino::new);
+        self.reset_hold_limit();
+        self.set_state(GameState::Paused);
+    }
+
+    // quit right away or ask first when quitting needs confirming
+    fn quit_from_menu(&mut self) {
+        if self.settings.confirm_quit {
+            self.confirm_quit = Some(menus::ConfirmQuitState::new());
+        } else {
             self.set_state(GameState::Quit);
         }
     }
 
-    fn paused_item_selected(&mut self) {
+    fn confirm_quit_action(&mut self, action: MenuAction) {
+        let Some(confirm_quit) = self.confirm_quit.as_mut() else {
+            return;
+        };
+        match action {
+            MenuAction::Select if confirm_quit.quit_selected() => self.set_state(GameState::Quit),
+            MenuAction::Select | MenuAction::Back => self.confirm_quit = None,
+            MenuAction::Up => confirm_quit.previous(),
+            MenuAction::Down => confirm_quit.next(),
+            _ => {}
+        }
+    }
+
+    // remember the mode so it's highlighted on the menu next time
+    fn set_last_mode(&mut self, ctx: &Context, mode: LastMode) {
+        self.menu_state.select_mode(mode);
+        if self.settings.last_mode != mode {
+            self.settings.last_mode = mode;
+            self.settings.save(&self.profile_dir(ctx));
+        }
+    }
+
+    fn start_game(&mut self, seed: Option<u64>) {
+        self.rustomino_bag = self.new_rustomino_bag(seed);
+        self.reset_hold_limit();
+        self.mode = match self.settings.trainer_block {
+            Some(rtype) => GameMode::Trainer(rtype),
+            None => GameMode::Marathon,
+        };
+        self.resume();
+        self.start_countdown();
+    }
+
+    // play again, retry the seed, head back to the menu or quit from the game over screen
+    fn game_over_action(&mut self, action: MenuAction) {
+        match action {
+            MenuAction::Select => match self.game_over_menu.selected_item() {
+                GameOverItem::PlayAgain => {
+                    self.new_game();
+                    self.start_game(None);
+                }
+                GameOverItem::RetrySeed => self.new_game_with_seed(self.rustomino_bag.seed()),
+                GameOverItem::Menu => self.new_game(),
+                GameOverItem::Quit => self.set_state(GameState::Quit),
+            },
+            MenuAction::Back => self.new_game(),
+            MenuAction::Up => self.game_over_menu.previous(),
+            MenuAction::Down => self.game_over_menu.next(),
+            _ => {}
+        }
+    }
+
+    // start another game with the same block sequence as the one that just ended
+    fn new_game_with_seed(&mut self, seed: u64) {
+        log::info!("retrying seed {}", seed);
+        self.new_game();
+        self.start_game(Some(seed));
+    }
+
+    // navigate the menu for the current state
+    fn menu_action(&mut self, ctx: &Context, action: MenuAction) {
+        match self.state {
+            GameState::Menu if self.confirm_quit.is_some() => self.confirm_quit_action(action),
+            GameState::Menu if self.scenario_menu.is_some() => self.scenario_action(ctx, action),
+            GameState::Menu => match action {
+                MenuAction::Select => self.menu_item_selected(ctx),
+                MenuAction::Back => self.quit_from_menu(),
+                MenuAction::Up => self.menu_state.previous(),
+                MenuAction::Down => self.menu_state.next(),
+                _ => {}
+            },
+            GameState::Paused => match action {
+                MenuAction::Select => self.paused_item_selected(ctx),
+                MenuAction::Back => {
+                    self.paused_state.reset_selection();
+                    self.resume();
+                }
+                MenuAction::Up => self.paused_state.previous(),
+                MenuAction::Down => self.paused_state.next(),
+                _ => {}
+            },
+            GameState::Options => match action {
+                MenuAction::Back => {
+                    self.settings.save(&self.profile_dir(ctx));
+                    self.set_state(self.previous_state);
+                }
+                MenuAction::Up => self.options_state.previous(),
+                MenuAction::Down => self.options_state.next(),
+                MenuAction::Left | MenuAction::Right
+                    if self.options_state.selected_item() == OptionsItem::Profile =>
+                {
+                    self.change_profile(ctx, action == MenuAction::Right);
+                }
+                MenuAction::Left => self.options_item_adjusted(false),
+                MenuAction::Right => self.options_item_adjusted(true),
+                MenuAction::Select
+                    if self.options_state.selected_item() == OptionsItem::ResetRecords =>
+                {
+                    self.reset_records(ctx);
+                }
+                MenuAction::Select => {}
+            },
+            _ => {}
+        }
+    }
+
+    fn paused_item_selected(&mut self, ctx: &Context) {
         if self.paused_state.selected() == 0 {
             self.resume();
             self.paused_state.reset_selection();
         } else if self.paused_state.selected() == 1 {
             self.set_state(GameState::Options);
         } else if self.paused_state.selected() == 2 {
+            // leaving the game saves it so it can be continued from the menu
+            let saved = self.save_game(ctx);
+            self.save_records(ctx);
             self.new_game();
             self.paused_state.reset_selection();
+            if saved {
+                self.menu_state = menus::MenuState::new(true);
+            }
         } else if self.paused_state.selected() == 3 {
+            self.save_game(ctx);
+            self.save_records(ctx);
             self.set_state(GameState::Quit);
         }
     }
 
-    fn handle_playing_inputs(&mut self) {
+    fn options_item_adjusted(&mut self, increase: bool) {
+        match self.options_state.selected_item() {
+            OptionsItem::MusicVolume => self.change_music_volume(increase),
+            OptionsItem::LockdownDelay => {
+                let step = if increase {
+                    settings::LOCKDOWN_DELAY_STEP
+                } else {
+                    -settings::LOCKDOWN_DELAY_STEP
+                };
+                self.settings.lockdown_delay = (self.settings.lockdown_delay + step)
+                    .clamp(settings::LOCKDOWN_DELAY_MIN, settings::LOCKDOWN_DELAY_MAX);
+                log::info!("lockdown delay set to {:.2}", self.settings.lockdown_delay);
+            }
+            OptionsItem::SpawnDelay => {
+                let delay = adjusted(
+                    self.settings.spawn_delay,
+                    settings::SPAWN_DELAY_STEP,
+                    increase,
+                );
+                // snap to the step so stepping back down reaches exactly zero
+                self.settings.spawn_delay = ((delay / settings::SPAWN_DELAY_STEP).round()
+                    * settings::SPAWN_DELAY_STEP)
+                    .clamp(0.0, settings::SPAWN_DELAY_MAX);
+                log::info!("spawn delay set to {:.2}", self.settings.spawn_delay);
+            }
+            OptionsItem::Das => {
+                self.settings.das = adjusted(self.settings.das, settings::DAS_STEP, increase)
+                    .clamp(0.0, settings::DAS_MAX);
+                log::info!("das set to {:.3}", self.settings.das);
+            }
+            OptionsItem::Arr => {
+                self.settings.arr = adjusted(self.settings.arr, settings::ARR_STEP, increase)
+                    .clamp(0.0, settings::ARR_MAX);
+                log::info!("arr set to {:.3}", self.settings.arr);
+            }
+            OptionsItem::SoftDropDas => {
+                self.settings.soft_drop_das =
+                    adjusted(self.settings.soft_drop_das, settings::DAS_STEP, increase)
+                        .clamp(0.0, settings::DAS_MAX);
+                log::info!("soft drop das set to {:.3}", self.settings.soft_drop_das);
+            }
+            OptionsItem::SoftDropArr => {
+                self.settings.soft_drop_arr =
+                    adjusted(self.settings.soft_drop_arr, settings::ARR_STEP, increase)
+                        .clamp(0.0, settings::ARR_MAX);
+                log::info!("soft drop arr set to {:.3}", self.settings.soft_drop_arr);
+            }
+            OptionsItem::LockdownMaxResets => {
+                self.settings.lockdown_max_resets = if increase {
+                    (self.settings.lockdown_max_resets + 1).min(settings::LOCKDOWN_MAX_RESETS_MAX)
+                } else {
+                    self.settings.lockdown_max_resets.saturating_sub(1)
+                };
+                log::info!(
+                    "lockdown max resets set to {}",
+                    self.settings.lockdown_max_resets
+                );
+            }
+            OptionsItem::LockdownReset => {
+                self.settings.lockdown_reset = self.settings.lockdown_reset.toggled();
+                log::info!("lockdown reset set to {:?}", self.settings.lockdown_reset);
+            }
+            OptionsItem::MaxGroundTime => {
+                let step = if increase {
+                    settings::MAX_GROUND_TIME_STEP
+                } else {
+                    -settings::MAX_GROUND_TIME_STEP
+                };
+                self.settings.max_ground_time = (self.settings.max_ground_time + step)
+                    .clamp(0.0, settings::MAX_GROUND_TIME_MAX);
+                log::info!(
+                    "max ground time set to {:.0}",
+                    self.settings.max_ground_time
+                );
+            }
+            OptionsItem::ReduceMotion => {
+                self.settings.reduce_motion = !self.settings.reduce_motion;
+                log::info!("reduce motion set to {}", self.settings.reduce_motion);
+            }
+            OptionsItem::Scoring => {
+                self.settings.scoring = self.settings.scoring.toggled();
+                self.scoring = ScoringConfig::for_scoring(self.settings.scoring);
+                log::info!("scoring set to {:?}", self.settings.scoring);
+            }
+            OptionsItem::AllSpin => {
+                self.settings.all_spin = !self.settings.all_spin;
+                log::info!("all spin set to {}", self.settings.all_spin);
+            }
+            OptionsItem::GravityCurve => {
+                self.settings.gravity_curve = self.settings.gravity_curve.next();
+                log::info!("gravity curve set to {:?}", self.settings.gravity_curve);
+                // apply the new curve to the game in progress
+                self.gravity_delay =
+                    gravity_delay(self.gravity_level(), self.settings.gravity_curve);
+            }
+            OptionsItem::SpeedCap => {
+                self.settings.speed_cap = if increase {
+                    (self.settings.speed_cap + 1).min(settings::SPEED_CAP_MAX)
+                } else {
+                    self.settings.speed_cap.saturating_sub(1)
+                };
+                log::info!("speed cap set to level {}", self.settings.speed_cap);
+                self.gravity_delay =
+                    gravity_delay(self.gravity_level(), self.settings.gravity_curve);
+            }
+            OptionsItem::ShowBufferRows => {
+                self.settings.show_buffer_rows = !self.settings.show_buffer_rows;
+                log::info!("show buffer rows set to {}", self.settings.show_buffer_rows);
+                self.rebuild_view_settings();
+            }
+            // records are only reset by selecting the item
+            // and profiles are changed in menu_action where their files can be read
+            OptionsItem::ResetRecords | OptionsItem::Profile => {}
+            OptionsItem::HideStaging => {
+                self.settings.hide_staging = !self.settings.hide_staging;
+                log::info!("hide staging set to {}", self.settings.hide_staging);
+                self.rebuild_view_settings();
+            }
+            OptionsItem::InfiniteHold => {
+                self.settings.infinite_hold = !self.settings.infinite_hold;
+                log::info!("infinite hold set to {}", self.settings.infinite_hold);
+            }
+            OptionsItem::SpawnFlatUp => {
+                self.settings.spawn_flat_up = !self.settings.spawn_flat_up;
+                log::info!("spawn flat side up set to {}", self.settings.spawn_flat_up);
+            }
+            OptionsItem::DangerRows => {
+                self.settings.danger_rows = if increase {
+                    (self.settings.danger_rows + 1).min(settings::DANGER_ROWS_MAX)
+                } else {
+                    self.settings.danger_rows.saturating_sub(1)
+                };
+                log::info!("danger rows set to {}", self.settings.danger_rows);
+            }
+            OptionsItem::Metronome => {
+                self.settings.metronome_bpm = if increase {
+                    (self.settings.metronome_bpm + settings::METRONOME_BPM_STEP)
+                        .min(settings::METRONOME_BPM_MAX)
+                } else {
+                    self.settings
+                        .metronome_bpm
+                        .saturating_sub(settings::METRONOME_BPM_STEP)
+                };
+                log::info!("metronome set to {} bpm", self.settings.metronome_bpm);
+            }
+            OptionsItem::ResetsSound => {
+                self.settings.resets_exhausted_sound = !self.settings.resets_exhausted_sound;
+                log::info!(
+                    "resets exhausted sound set to {}",
+                    self.settings.resets_exhausted_sound
+                );
+            }
+            OptionsItem::DangerSound => {
+                self.settings.danger_sound = !self.settings.danger_sound;
+                log::info!("danger sound set to {}", self.settings.danger_sound);
+            }
+            OptionsItem::LockPitch => {
+                self.settings.lock_pitch_variation = !self.settings.lock_pitch_variation;
+                log::info!(
+                    "lock pitch variation set to {}",
+                    self.settings.lock_pitch_variation
+                );
+            }
+            OptionsItem::DasCarry => {
+                self.settings.das_carry = !self.settings.das_carry;
+                log::info!("das carry set to {}", self.settings.das_carry);
+            }
+            OptionsItem::StickyRotation => {
+                self.settings.sticky_rotation = !self.settings.sticky_rotation;
+                log::info!("sticky rotation set to {}", self.settings.sticky_rotation);
+            }
+            OptionsItem::Frame => {
+                self.settings.show_frame = !self.settings.show_frame;
+                log::info!("show frame set to {}", self.settings.show_frame);
+                self.rebuild_view_settings();
+            }
+            OptionsItem::GhostColor => {
+                self.settings.colored_ghost = !self.settings.colored_ghost;
+                log::info!("colored ghost set to {}", self.settings.colored_ghost);
+                self.rebuild_view_settings();
+            }
+            OptionsItem::MonoBlocks => {
+                self.settings.mono_blocks = !self.settings.mono_blocks;
+                log::info!("mono blocks set to {}", self.settings.mono_blocks);
+                self.rebuild_view_settings();
+            }
+            OptionsItem::SpawnGuide => {
+                self.settings.show_spawn_guide = !self.settings.show_spawn_guide;
+                log::info!("show spawn guide set to {}", self.settings.show_spawn_guide);
+            }
+            OptionsItem::Drought => {
+                self.settings.show_drought = !self.settings.show_drought;
+                log::info!("show drought set to {}", self.settings.show_drought);
+            }
+            OptionsItem::LockTimer => {
+                self.settings.show_lock_timer = !self.settings.show_lock_timer;
+                log::info!("show lock timer set to {}", self.settings.show_lock_timer);
+            }
+            OptionsItem::Heatmap => {
+                self.settings.show_heatmap = !self.settings.show_heatmap;
+                log::info!("show heatmap set to {}", self.settings.show_heatmap);
+            }
+            OptionsItem::DropHint => {
+                self.settings.show_drop_hint = !self.settings.show_drop_hint;
+                log::info!("show drop hint set to {}", self.settings.show_drop_hint);
+            }
+            OptionsItem::HoldLimit => {
+                self.settings.hold_limit = if increase {
+                    (self.settings.hold_limit + 1).min(settings::HOLD_LIMIT_MAX)
+                } else {
+                    self.settings.hold_limit.saturating_sub(1)
+                };
+                log::info!("hold limit set to {}", self.settings.hold_limit);
+            }
+            OptionsItem::HoldLimitScope => {
+                self.settings.hold_limit_scope = self.settings.hold_limit_scope.toggled();
+                log::info!(
+                    "hold limit scope set to {:?}",
+                    self.settings.hold_limit_scope
+                );
+            }
+            OptionsItem::LockOnContact => {
+                self.settings.lock_on_contact = !self.settings.lock_on_contact;
+                log::info!("lock on contact set to {}", self.settings.lock_on_contact);
+            }
+            OptionsItem::HardDropLock => {
+                self.settings.hard_drop_lock = !self.settings.hard_drop_lock;
+                log::info!("hard drop lock set to {}", self.settings.hard_drop_lock);
+            }
+            OptionsItem::HudScale => {
+                self.settings.hud_scale = self.settings.hud_scale.next();
+                log::info!("hud scale set to {:?}", self.settings.hud_scale);
+                self.rebuild_view_settings();
+            }
+            OptionsItem::Trainer => {
+                self.settings.trainer_block =
+                    settings::next_trainer_block(self.settings.trainer_block);
+                log::info!("trainer block set to {:?}", self.settings.trainer_block);
+            }
+            OptionsItem::SoftDropSpeed => {
+                self.settings.soft_drop_speed = self.settings.soft_drop_speed.next();
+                log::info!("soft drop speed set to {:?}", self.settings.soft_drop_speed);
+            }
+            OptionsItem::ConfirmQuit => {
+                self.settings.confirm_quit = !self.settings.confirm_quit;
+                log::info!("confirm quit set to {}", self.settings.confirm_quit);
+            }
+            OptionsItem::KeyLayout => {
+                let previous = self.settings.key_layout;
+                self.settings.key_layout = self.settings.key_layout.next();
+                if self.apply_key_preset() {
+                    log::info!("key layout set to {:?}", self.settings.key_layout);
+                } else {
+                    self.settings.key_layout = previous;
+                }
+            }
+            OptionsItem::SwapDropKeys => {
+                self.settings.swap_drop_keys = !self.settings.swap_drop_keys;
+                if self.apply_key_preset() {
+                    log::info!("swap drop keys set to {}", self.settings.swap_drop_keys);
+                } else {
+                    self.settings.swap_drop_keys = !self.settings.swap_drop_keys;
+                }
+            }
+            OptionsItem::Theme => {
+                self.settings.theme = self.settings.theme.next();
+                log::info!("theme set to {:?}", self.settings.theme);
+                self.rebuild_view_settings();
+            }
+            OptionsItem::Background => {
+                self.settings.background_color = self.settings.background_color.next();
+                log::info!(
+                    "background color set to {:?}",
+                    self.settings.background_color
+                );
+            }
+        }
+        self.options_state
+            .update(&self.settings, self.music_volume, &self.profile);
+    }
+
+    fn change_music_volume(&mut self, increase: bool) {
+        if increase {
+            self.music_volume += MUSIC_VOLUME_CHANGE;
+        } else {
+            self.music_volume -= MUSIC_VOLUME_CHANGE;
+        }
+        self.music_volume = self.music_volume.clamp(0.0, 1.0);
+        // update_music glides the music to the new volume
+        log::info!("music volume set to {:.2}", self.music_volume);
+    }
+
+    // rebind the keys from the key settings, returns false if the
+    // bindings they make aren't playable so the setting can be put back
+    fn apply_key_preset(&mut self) -> bool {
+        let preset = KeyPreset::from_settings(&self.settings);
+        let applied = self.controls.set_preset(preset);
+        if !applied {
+            log::warn!("keeping the current keys, {:?} isn't playable", preset);
+        }
+        applied
+    }
+
+    // apply view related settings to the current window size
+    fn rebuild_view_settings(&mut self) {
+        self.view_settings = draw::ViewSettings::new(
+            self.view_settings.view_rect.w,
+            self.view_settings.view_rect.h,
+            self.settings.show_buffer_rows,
+            self.settings.hide_staging,
+            self.settings.theme.theme(),
+            self.settings.hud_scale.scale(),
+            self.settings.colored_ghost,
+            self.settings.mono_blocks,
+            self.settings.show_frame,
+        );
+    }
+
+    fn handle_playing_inputs(&mut self, delta_time: f64) {
         // iterate through the controls
+        // timing uses the simulation time so pauses and catch-up behave like gravity
         for control in Control::iter() {
             match self.controls.input_states[&control] {
                 controls::InputState::Down(time) => {
-                    let duration = time.elapsed().as_secs_f64();
-                    match control.action_delay() {
+                    let duration = time + delta_time;
+                    match control.action_delay(&self.settings) {
                         Some(delay) if duration >= delay => {
                             log::debug!("action delay met for {:?}", control);
                             self.controls.input_states.entry(control).and_modify(|e| {
-                                *e = controls::InputState::Held(time::Instant::now());
+                                *e = controls::InputState::Held(0.0);
+                            });
+                            self.handle_control(control);
+                        }
+                        Some(_) => {
+                            self.controls.input_states.entry(control).and_modify(|e| {
+                                *e = controls::InputState::Down(duration);
+                            });
+                        }
+                        None if control.continuous(&self.settings) => {
+                            self.controls.input_states.entry(control).and_modify(|e| {
+                                *e = controls::InputState::Down(duration);
                             });
-                            self.control_handler(control)(self);
                         }
                         None => {
                             self.controls.input_states.entry(control).and_modify(|e| {
                                 *e = controls::InputState::Up;
                             });
                         }
-                        _ => (),
                     }
                 }
                 controls::InputState::Held(time) => {
-                    let duration = time.elapsed().as_secs_f64();
-                    match control.action_repeat_delay() {
+                    let duration = time + delta_time;
+                    match control.action_repeat_delay(&self.settings) {
+                        // stay charged while waiting for the next block to spawn
+                        Some(delay)
+                            if duration >= delay && self.playfield.active_rustomino.is_none() =>
+                        {
+                            self.controls.input_states.entry(control).and_modify(|e| {
+                                *e = controls::InputState::Held(duration);
+                            });
+                        }
                         Some(delay) if duration >= delay => {
                             log::debug!("action repeat delay met for {:?}", control);
                             self.controls.input_states.entry(control).and_modify(|e| {
-                                *e = controls::InputState::Held(time::Instant::now());
+                                *e = controls::InputState::Held(0.0);
+                            });
+                            self.handle_control(control);
+                        }
+                        _ => {
+                            self.controls.input_states.entry(control).and_modify(|e| {
+                                *e = controls::InputState::Held(duration);
                             });
-                            self.control_handler(control)(self);
                         }
-                        _ => (),
                     }
                 }
                 _ => (),
             }
         }
     }
+    /// returns a textual snapshot of the game for attaching to bug reports
+    /// the bag is filled far enough to list the upcoming blocks
+    pub fn snapshot(&mut self) -> String {
+        let active = match &self.playfield.active_rustomino {
+            Some(rustomino) => format!(
+                "{:?} facing: {:?} {:?} translation: {:?} blocks: {:?}",
+                rustomino.rtype,
+                rustomino.rotation.direction(),
+                rustomino.state,
+                rustomino.translation,
+                rustomino.blocks
+            ),
+            None => "None".to_string(),
+        };
+        format!(
+            "version: {}\nstate: {:?}\nmode: {:?}\nseed: {}\ndrawn: {}\nlevel: {}\nscore: {}\nlines cleared: {}\nlockdown resets: {}\nactive: {}\nnext: {:?}\nupcoming: {:?}\nheld: {:?}\nboard code: {}\nplayfield:\n{}\n",
+            env!("CARGO_PKG_VERSION"),
+            self.state,
+            self.mode,
+            self.rustomino_bag.seed(),
+            self.rustomino_bag.drawn(),
+            self.level,
+            self.score,
+            self.total_lines_cleared,
+            self.lockdown_resets,
+            active,
+            self.next_rustomino.as_ref().map(|r| r.rtype),
+            self.rustomino_bag.peek(SNAPSHOT_UPCOMING),
+            self.held_rustomino.as_ref().map(|r| r.rtype),
+            board_code::encode(&self.playfield.slots),
+            self.playfield
+        )
+    }
+
+    // debug aid, empties the playfield without ending the game
+    // the next block spawns on the clean board
+    fn clear_board(&mut self) {
+        log::info!("debug: clearing the playfield");
+        self.playfield = Playfield::new();
+        self.lockdown_resets = 0;
+        self.ground_time = 0.0;
+    }
+
+    // write a snapshot of the game to the user's data directory
+    fn save_snapshot(&mut self, ctx: &Context) {
+        let dir = ctx.fs.user_data_dir();
+        let path = dir.join(SNAPSHOT_FILE);
+        match std::fs::create_dir_all(dir).and_then(|_| std::fs::write(&path, self.snapshot())) {
+            Ok(_) => log::info!("saved game snapshot to {:?}", path),
+            Err(e) => log::warn!("unable to save game snapshot to {:?}: {}", path, e),
+        }
+    }
+
+    // seconds between metronome beats, None when the metronome is off
+    fn metronome_beat(&self) -> Option<f64> {
+        (self.settings.metronome_bpm > 0).then(|| 60.0 / self.settings.metronome_bpm as f64)
+    }
+
+    // how far through the current beat the play clock is, from 0.0 to 1.0
+    fn metronome_phase(&self) -> Option<f64> {
+        if self.countdown.is_some() {
+            return None;
+        }
+        self.metronome_beat()
+            .map(|beat| (self.play_time / beat).fract())
+    }
+
+    // tick whenever the play clock crosses into a new beat
+    fn update_metronome(&mut self, delta_time: f64) {
+        let Some(beat) = self.metronome_beat() else {
+            return;
+        };
+        let previous = ((self.play_time - delta_time) / beat).floor();
+        if (self.play_time / beat).floor() > previous {
+            self.sound_effects.push(SoundEffect::Tick);
+        }
+    }
+
+    // checks if the stack is close to the top while playing
+    // and starts or stops the warning sound when that changes
+    fn update_danger(&mut self, ctx: &Context) -> GameResult {
+        let top = PLAYFIELD_SIZE[1] as usize;
+        let danger = self.state == GameState::Playing
+            && self.settings.danger_rows > 0
+            && self
+                .playfield
+                .column_heights()
+                .iter()
+                .any(|height| *height + self.settings.danger_rows >= top);
+        if danger == self.danger {
+            return Ok(());
+        }
+        log::info!("stack danger: {}", danger);
+        self.danger = danger;
+        let Some(sound) = self.assets.as_mut().and_then(|a| a.danger.as_mut()) else {
+            return Ok(());
+        };
+        if danger && self.settings.danger_sound {
+            sound.play(ctx)?;
+        } else {
+            sound.stop(ctx)?;
+        }
+        Ok(())
+    }
+
+    // crossfade towards the menu or gameplay music for the current state
+    // and glide towards the chosen music volume
+    fn update_music(&mut self, delta_time: f64) {
+        let target = match self.state {
+            GameState::Playing | GameState::GameOver => 1.0,
+            _ => 0.0,
+        };
+        if self.music_mix == target && self.played_music_volume == self.music_volume {
+            return;
+        }
+        let step = (delta_time / MUSIC_CROSSFADE_TIME) as f32;
+        self.music_mix = step_towards(self.music_mix, target, step);
+        // larger changes glide faster so any change takes about the ramp time
+        let distance = (self.music_volume - self.played_music_volume)
+            .abs()
+            .max(MUSIC_VOLUME_CHANGE);
+        let step = distance * (delta_time / MUSIC_VOLUME_RAMP_TIME) as f32;
+        self.played_music_volume = step_towards(self.played_music_volume, self.music_volume, step);
+        if let Some(assets) = self.assets.as_mut() {
+            assets.set_music_mix(self.played_music_volume, self.music_mix);
+        }
+    }
+
     fn set_state(&mut self, state: GameState) {
         log::info!(
             "setting state to {:?} previous state {:?}",
             state,
             self.state
         );
+        self.start_transition(self.state, state);
         self.previous_state = self.state;
         self.state = state;
     }
+
+    // fade in when moving between the menu, the game and the game over screen
+    // the pause menu and options are overlays so they switch instantly
+    fn start_transition(&mut self, from: GameState, to: GameState) {
+        if self.settings.reduce_motion {
+            return;
+        }
+        if matches!(
+            (from, to),
+            (GameState::Menu | GameState::GameOver, GameState::Playing)
+                | (GameState::Playing, GameState::GameOver)
+                | (GameState::Paused | GameState::GameOver, GameState::Menu)
+        ) {
+            self.transition = Some(0.0);
+        }
+    }
+
+    fn update_transition(&mut self, delta_time: f64) {
+        self.transition = self
+            .transition
+            .map(|time| time + delta_time)
+            .filter(|time| *time < TRANSITION_TIME);
+    }
 }
 
 impl EventHandler for BlocksState {
@@ -535,37 +2296,120 @@ impl EventHandler for BlocksState {
         // limit game to 60fps
         while ctx.time.check_update_time(DESIRED_FPS) {
             let delta_time = 1.0 / (DESIRED_FPS as f64);
+            self.update_music(delta_time);
+            self.update_danger(ctx)?;
+            self.update_transition(delta_time);
             // handle the game states
             match self.state {
+                // gameplay waits for the screen to finish fading in
+                GameState::Playing if self.transition.is_some() => {}
                 GameState::Playing => {
-                    self.handle_playing_inputs();
+                    // the update still uses a whole tick so the game doesn't
+                    // try to catch up when it's unfrozen
+                    if cfg!(debug_assertions) && self.debug_stepping.skip_update() {
+                        continue;
+                    }
+                    self.animations.update(delta_time);
+                    // spawn the first rustomino but hold it in place
+                    // until the countdown has finished
+                    if self.countdown.is_some() {
+                        self.ready_playfield();
+                        self.update_countdown(delta_time);
+                        continue;
+                    }
+                    self.play_time += delta_time;
+                    self.update_metronome(delta_time);
+                    self.add_scheduled_garbage();
+                    if self.state != GameState::Playing {
+                        continue;
+                    }
+                    // the demo player uses the same controls as a human
+                    let next = self.next_rustomino.as_ref().map(|r| r.rtype);
+                    let demo_control = self
+                        .demo
+                        .as_mut()
+                        .and_then(|demo| demo.update(delta_time, &self.playfield, next));
+                    if let Some(control) = demo_control {
+                        self.handle_control(control);
+                    }
+                    self.handle_playing_inputs(delta_time);
                     if self.ready_playfield() {
                         self.playing_update(delta_time);
                     }
+                    self.update_popups(delta_time);
                 }
                 GameState::GameOver if self.previous_state != self.state => {
                     // play game over sound if we've just changed state
-                    self.assets.game_over.play(ctx)?;
+                    // or the high score jingle when the game set a new best
+                    if let Some(assets) = self.assets.as_mut() {
+                        match assets.high_score.as_mut() {
+                            Some(high_score) if self.new_high_score => high_score.play(ctx)?,
+                            _ => assets.game_over.play(ctx)?,
+                        }
+                    }
+                    if self.new_high_score {
+                        high_score::save(&self.profile_dir(ctx), self.high_score);
+                    }
+                    self.save_records(ctx);
                     self.previous_state = GameState::GameOver;
+                    // start the game over effects along with the sound
+                    self.game_over_time = 0.0;
                 }
                 GameState::Menu => {}
+                // popups and the hold, preview and spawn animations only advance
+                // with the playing update so they stay frozen behind the pause menu
                 GameState::Paused => {}
-                GameState::GameOver => {}
+                GameState::GameOver => {
+                    self.game_over_time += delta_time;
+                }
                 GameState::Options => {}
                 GameState::Quit => ctx.request_quit(),
             }
         }
+
+        // play any sound effects triggered by the game
+        if let Some(assets) = self.assets.as_mut() {
+            for sound_effect in self.sound_effects.drain(..) {
+                assets.play_sound_effect(ctx, sound_effect)?;
+            }
+        }
         Ok(())
     }
 
     fn draw(&mut self, ctx: &mut ggez::Context) -> GameResult {
-        let mut canvas = graphics::Canvas::from_frame(ctx, BACKGROUND_COLOR);
+        if cfg!(debug_assertions) {
+            self.frame_capture.capture(ctx)?;
+        }
+        let background_color = self
+            .settings
+            .background_color
+            .color(&self.view_settings.theme);
+        let mut canvas = graphics::Canvas::from_frame(ctx, background_color);
+        // the optional background image covers the background color
+        if let Some(background) = self.assets.as_ref().and_then(|a| a.background.as_ref()) {
+            draw::draw_background_image(&mut canvas, background, &self.view_settings);
+        }
 
         // handle the game states
         match self.state {
-            GameState::Menu => {
-                draw::draw_menu(ctx, &mut canvas, &self.menu_state, &self.view_settings)?;
-            }
+            GameState::Menu => match (&self.confirm_quit, &self.scenario_menu) {
+                (Some(confirm_quit), _) => {
+                    draw::draw_confirm_quit(ctx, &mut canvas, confirm_quit, &self.view_settings)?;
+                }
+                (None, Some(scenario_menu)) => {
+                    draw::draw_scenarios(ctx, &mut canvas, scenario_menu, &self.view_settings)?;
+                }
+                (None, None) => {
+                    draw::draw_menu(ctx, &mut canvas, &self.menu_state, &self.view_settings)?;
+                    draw::draw_records(
+                        ctx,
+                        &mut canvas,
+                        &self.records,
+                        &self.session_records,
+                        &self.view_settings,
+                    )?;
+                }
+            },
             GameState::Playing => {
                 draw::draw_playing(
                     ctx,
@@ -573,16 +2417,82 @@ impl EventHandler for BlocksState {
                     &self.playfield,
                     &self.next_rustomino,
                     &self.held_rustomino,
+                    self.hold_available(),
+                    &self.animations(),
                     &self.view_settings,
+                    self.danger,
                     false,
                 )?;
                 draw::draw_playing_text(
                     ctx,
                     &mut canvas,
                     self.level,
+                    self.level_progress(),
                     self.score,
+                    self.lock_timer(),
+                    &self.view_settings,
+                )?;
+                if self.settings.show_heatmap {
+                    draw::draw_heatmap(ctx, &mut canvas, &self.lock_counts, &self.view_settings)?;
+                }
+                if self.settings.show_drop_hint {
+                    draw::draw_drop_hint(ctx, &mut canvas, &self.playfield, &self.view_settings)?;
+                }
+                if self.settings.show_spawn_guide {
+                    if let Some(next) = self.next_rustomino.clone() {
+                        draw::draw_spawn_guide(
+                            ctx,
+                            &mut canvas,
+                            &self.spawn_orientation(next),
+                            &self.view_settings,
+                        )?;
+                    }
+                }
+                if let Some(holds) = self.holds_remaining {
+                    draw::draw_holds_remaining(ctx, &mut canvas, holds, &self.view_settings)?;
+                }
+                if self.settings.show_drought {
+                    draw::draw_drought(
+                        ctx,
+                        &mut canvas,
+                        self.rustomino_bag.drought(RustominoType::I),
+                        &self.view_settings,
+                    )?;
+                }
+                if let Some(phase) = self.metronome_phase() {
+                    draw::draw_metronome(ctx, &mut canvas, phase, &self.view_settings)?;
+                }
+                draw::draw_popups(
+                    ctx,
+                    &mut canvas,
+                    &self.popups,
                     &self.view_settings,
+                    self.settings.reduce_motion,
                 )?;
+                if let Some(text) = self.countdown_text() {
+                    draw::draw_countdown(ctx, &mut canvas, text, &self.view_settings)?;
+                }
+                if self.show_controls_help {
+                    draw::draw_controls_help(
+                        ctx,
+                        &mut canvas,
+                        &self.controls.input_map,
+                        &self.view_settings,
+                    )?;
+                }
+                if self.move_history.visible {
+                    draw::draw_move_history(
+                        ctx,
+                        &mut canvas,
+                        &self.move_history.lines(),
+                        &self.view_settings,
+                    )?;
+                }
+                if self.show_active_position {
+                    if let Some(active) = &self.playfield.active_rustomino {
+                        draw::draw_active_position(ctx, &mut canvas, active, &self.view_settings)?;
+                    }
+                }
             }
             GameState::Paused => {
                 draw::draw_playing(
@@ -591,16 +2501,52 @@ impl EventHandler for BlocksState {
                     &self.playfield,
                     &self.next_rustomino,
                     &self.held_rustomino,
+                    self.hold_available(),
+                    &self.animations(),
                     &self.view_settings,
+                    self.danger,
                     false,
                 )?;
                 draw::draw_playing_text(
                     ctx,
                     &mut canvas,
                     self.level,
+                    self.level_progress(),
                     self.score,
+                    self.lock_timer(),
                     &self.view_settings,
                 )?;
+                if self.settings.show_drop_hint {
+                    draw::draw_drop_hint(ctx, &mut canvas, &self.playfield, &self.view_settings)?;
+                }
+                if self.settings.show_spawn_guide {
+                    if let Some(next) = self.next_rustomino.clone() {
+                        draw::draw_spawn_guide(
+                            ctx,
+                            &mut canvas,
+                            &self.spawn_orientation(next),
+                            &self.view_settings,
+                        )?;
+                    }
+                }
+                if let Some(holds) = self.holds_remaining {
+                    draw::draw_holds_remaining(ctx, &mut canvas, holds, &self.view_settings)?;
+                }
+                if self.settings.show_drought {
+                    draw::draw_drought(
+                        ctx,
+                        &mut canvas,
+                        self.rustomino_bag.drought(RustominoType::I),
+                        &self.view_settings,
+                    )?;
+                }
+                draw::draw_popups(
+                    ctx,
+                    &mut canvas,
+                    &self.popups,
+                    &self.view_settings,
+                    self.settings.reduce_motion,
+                )?;
                 draw::draw_paused(ctx, &mut canvas, &self.paused_state, &self.view_settings)?;
             }
             GameState::GameOver => {
@@ -611,24 +2557,75 @@ impl EventHandler for BlocksState {
                     &self.playfield,
                     &self.next_rustomino,
                     &self.held_rustomino,
+                    self.hold_available(),
+                    &self.animations(),
                     &self.view_settings,
+                    false,
                     true,
                 )?;
                 draw::draw_playing_text(
                     ctx,
                     &mut canvas,
                     self.level,
+                    self.level_progress(),
                     self.score,
+                    None,
                     &self.view_settings,
                 )?;
-                draw::draw_gameover(ctx, &mut canvas, &self.view_settings.view_rect)?;
+                // the heatmap shows where the whole game was played
+                if self.settings.show_heatmap {
+                    draw::draw_heatmap(ctx, &mut canvas, &self.lock_counts, &self.view_settings)?;
+                }
+                draw::draw_gameover(
+                    ctx,
+                    &mut canvas,
+                    &self.view_settings,
+                    self.game_over_time,
+                    self.game_over_reason.map(|r| r.description()),
+                    &format!("Seed {} - {}", self.rustomino_bag.seed(), self.mode.name()),
+                    &self.game_over_menu,
+                    self.settings.reduce_motion,
+                )?;
+                if self.new_high_score {
+                    draw::draw_new_high_score(
+                        ctx,
+                        &mut canvas,
+                        &self.view_settings,
+                        self.settings.reduce_motion,
+                    )?;
+                }
             }
             GameState::Options => {
-                draw::draw_options(ctx, &mut canvas, &self.view_settings.view_rect)?;
+                draw::draw_options(
+                    ctx,
+                    &mut canvas,
+                    &self.options_state,
+                    &self.view_settings.view_rect,
+                )?;
+                if matches!(
+                    self.options_state.selected_item(),
+                    OptionsItem::KeyLayout | OptionsItem::SwapDropKeys
+                ) {
+                    draw::draw_key_map_preview(
+                        ctx,
+                        &mut canvas,
+                        &self.controls.input_map,
+                        &self.view_settings.view_rect,
+                    )?;
+                }
             }
             GameState::Quit => {}
         }
 
+        if let Some(time) = self.transition {
+            draw::draw_transition(
+                ctx,
+                &mut canvas,
+                &self.view_settings,
+                1.0 - (time / TRANSITION_TIME) as f32,
+            )?;
+        }
+
         canvas.finish(ctx)?;
 
         ggez::timer::yield_now();
@@ -637,85 +2634,152 @@ impl EventHandler for BlocksState {
 
     // Handle key events.  These just map keyboard events
     // and alter our input state appropriately.
-    fn key_down_event(
-        &mut self,
-        _ctx: &mut Context,
-        input: KeyInput,
-        repeated: bool,
-    ) -> GameResult {
+    fn key_down_event(&mut self, ctx: &mut Context, input: KeyInput, repeated: bool) -> GameResult {
+        // save a snapshot of the game for bug reports
+        if input.keycode == Some(KeyCode::F9) && !repeated {
+            self.save_snapshot(ctx);
+        }
+        if cfg!(debug_assertions) && !repeated {
+            if let Some(keycode) = input.keycode {
+                self.debug_stepping.key_down(keycode);
+            }
+            if input.keycode == Some(KeyCode::F8) {
+                self.frame_capture.toggle(&self.settings.capture_dir);
+            }
+            if input.keycode == Some(KeyCode::F7) && self.state == GameState::Playing {
+                self.clear_board();
+            }
+            if input.keycode == Some(KeyCode::F6) {
+                self.move_history.visible = !self.move_history.visible;
+                log::info!("show move history: {}", self.move_history.visible);
+            }
+            if input.keycode == Some(KeyCode::F5) {
+                self.show_active_position = !self.show_active_position;
+                log::info!("show active position: {}", self.show_active_position);
+            }
+        }
+        // toggle the controls help without affecting the game
+        if input.keycode == Some(KeyCode::F1) && !repeated && self.state == GameState::Playing {
+            self.show_controls_help = !self.show_controls_help;
+            log::info!("show controls help: {}", self.show_controls_help);
+            return Ok(());
+        }
         match self.state {
             GameState::Playing => {
-                // pause the game immediately
-                // clear all other inputs and continue
-                if input.keycode == Some(KeyCode::Escape) {
-                    self.pause();
-                    self.controls.clear_inputs();
+                // any key takes over from the demo
+                if self.demo.is_some() {
+                    if !repeated {
+                        self.end_demo();
+                    }
+                    return Ok(());
+                }
+                // any key skips the new game countdown
+                if self.countdown.is_some() {
+                    if !repeated {
+                        log::info!("new game countdown skipped");
+                        self.countdown = None;
+                    }
+                    return Ok(());
                 }
                 if !repeated {
                     if let Some(keycode) = input.keycode {
                         if let Some(control) = self.controls.key_map.get(&keycode) {
-                            self.control_handler(*control)(self);
+                            // pause the game immediately
+                            // clear all other inputs and continue
+                            if *control == Control::Pause {
+                                self.pause();
+                                return Ok(());
+                            }
+                            self.handle_control(*control);
                         }
                     }
                     self.controls.set_pressed(input.keycode);
                 }
             }
-            GameState::Menu => {
-                // handle the user's inputs
-                if input.keycode == Some(KeyCode::Return) && !repeated {
-                    self.menu_item_selected();
-                }
-                if input.keycode == Some(KeyCode::Escape) && !repeated {
-                    self.set_state(GameState::Quit);
-                }
-                if input.keycode == Some(KeyCode::Up) && !repeated {
-                    self.menu_state.previous();
-                }
-                if input.keycode == Some(KeyCode::Down) && !repeated {
-                    self.menu_state.next();
-                }
-            }
-            GameState::Paused => {
-                if input.keycode == Some(KeyCode::Escape) && !repeated {
-                    self.paused_state.reset_selection();
-                    self.resume();
-                }
-                if input.keycode == Some(KeyCode::Return)
-                    || input.keycode == Some(KeyCode::NumpadEnter) && !repeated
-                {
-                    self.paused_item_selected();
-                }
-                if input.keycode == Some(KeyCode::Up) && !repeated {
-                    self.paused_state.previous();
-                }
-                if input.keycode == Some(KeyCode::Down) && !repeated {
-                    self.paused_state.next();
+            GameState::Menu | GameState::Paused => {
+                // the pause control also resumes the game
+                let action = match input.keycode.and_then(|k| self.controls.key_map.get(&k)) {
+                    Some(Control::Pause) if self.state == GameState::Paused => {
+                        Some(MenuAction::Back)
+                    }
+                    _ => input.keycode.and_then(MenuAction::from_key),
+                };
+                if let Some(action) = action {
+                    if !repeated || action.repeats() {
+                        self.menu_action(ctx, action);
+                    }
                 }
             }
             GameState::GameOver => {
-                self.new_game();
+                if let Some(action) = input.keycode.and_then(MenuAction::from_key) {
+                    if !repeated || action.repeats() {
+                        self.game_over_action(action);
+                    }
+                }
             }
             GameState::Options => {
-                if input.keycode == Some(KeyCode::Escape) && !repeated {
-                    self.set_state(self.previous_state);
+                if let Some(action) = input.keycode.and_then(MenuAction::from_key) {
+                    if !repeated || action.repeats() {
+                        self.menu_action(ctx, action);
+                    }
                 }
                 // volume down
                 if input.keycode == Some(KeyCode::Minus)
                     || input.keycode == Some(KeyCode::NumpadSubtract)
                 {
-                    self.music_volume -= MUSIC_VOLUME_CHANGE;
-                    self.music_volume = self.music_volume.clamp(0.0, 1.0);
-                    self.assets.music_1.set_volume(self.music_volume);
-                    log::info!("volume decreased to {:.2}", self.music_volume);
+                    self.change_music_volume(false);
+                    self.options_state
+                        .update(&self.settings, self.music_volume, &self.profile);
                 }
                 // volume up
                 if input.keycode == Some(KeyCode::Equals)
                     || input.keycode == Some(KeyCode::NumpadAdd)
                 {
-                    self.music_volume += MUSIC_VOLUME_CHANGE;
-                    self.music_volume = self.music_volume.clamp(0.0, 1.0);
-                    self.assets.music_1.set_volume(self.music_volume);
-                    log::info!("volume increase {:.2}", self.music_volume);
+                    self.change_music_volume(true);
+                    self.options_state
+                        .update(&self.settings, self.music_volume, &self.profile);
+                }
+            }
+            GameState::Quit => {}
+        }
+        Ok(())
+    }
+
+    fn gamepad_button_down_event(
+        &mut self,
+        ctx: &mut Context,
+        btn: Button,
+        _id: GamepadId,
+    ) -> GameResult {
+        let control = self.controls.button_map.get(&btn).copied();
+        match self.state {
+            GameState::Playing => {
+                // any button takes over from the demo
+                if self.demo.is_some() {
+                    self.end_demo();
+                    return Ok(());
+                }
+                // any button skips the new game countdown
+                if self.countdown.is_some() {
+                    log::info!("new game countdown skipped");
+                    self.countdown = None;
+                    return Ok(());
+                }
+                if control == Some(Control::Pause) {
+                    self.pause();
+                }
+            }
+            GameState::Paused if control == Some(Control::Pause) => {
+                self.menu_action(ctx, MenuAction::Back);
+            }
+            GameState::Menu | GameState::Paused | GameState::Options => {
+                if let Some(action) = MenuAction::from_button(btn) {
+                    self.menu_action(ctx, action);
+                }
+            }
+            GameState::GameOver => {
+                if let Some(action) = MenuAction::from_button(btn) {
+                    self.game_over_action(action);
                 }
             }
             GameState::Quit => {}
@@ -738,7 +2802,17 @@ impl EventHandler for BlocksState {
     }
 
     fn resize_event(&mut self, _ctx: &mut Context, width: f32, height: f32) -> GameResult {
-        self.view_settings = draw::ViewSettings::new(width, height);
+        self.view_settings = draw::ViewSettings::new(
+            width,
+            height,
+            self.settings.show_buffer_rows,
+            self.settings.hide_staging,
+            self.settings.theme.theme(),
+            self.settings.hud_scale.scale(),
+            self.settings.colored_ghost,
+            self.settings.mono_blocks,
+            self.settings.show_frame,
+        );
         Ok(())
     }
 
@@ -750,37 +2824,459 @@ impl EventHandler for BlocksState {
     }
 }
 
-fn score_cleared_lines(num_lines: usize, level: usize) -> usize {
-    // Single lines 100xlevel
-    // Double lines 300xlevel
-    // Triple lines 500xlevel
-    // Quad lines 800xlevel
-    (level + 1)
-        * match num_lines {
-            1 => SINGLE_LINE_SCORE,
-            2 => DOUBLE_LINE_SCORE,
-            3 => TRIPLE_LINE_SCORE,
-            4 => QUAD_SCORE,
-            _ => panic!("impossible number of lines cleared"),
-        }
+// the records are updated before scoring so the combo and back-to-back
+// counts include the clear being scored
+fn score_cleared_lines(
+    scoring: &ScoringConfig,
+    num_lines: usize,
+    t_spin: TSpin,
+    level: usize,
+    combo: usize,
+    back_to_back: usize,
+) -> usize {
+    let mut score = scoring.clear(num_lines, t_spin);
+    // a tetris or spin clear straight after another is worth more
+    if num_lines > 0 && back_to_back > 1 {
+        score = (score as f64 * scoring.back_to_back_multiplier) as usize;
+    }
+    // every clear in a row after the first adds to the combo bonus
+    let combo_bonus = scoring.combo_step * combo.saturating_sub(1);
+    (level + 1) * (score + combo_bonus)
 }
 
-// checks to see if ALL of the slots in the provided
-// slots array are above the playfield
-fn fully_out_of_bounds(&slots: &[IVec2; 4]) -> bool {
-    for slot in slots {
-        if slot[1] < PLAYFIELD_SIZE[1] {
-            return false;
-        }
+// the name of a t-spin shown to the player
+fn t_spin_name(t_spin: TSpin, num_lines: usize) -> String {
+    let name = match t_spin {
+        TSpin::Mini => "Mini T-Spin",
+        _ => "T-Spin",
+    };
+    with_lines_cleared(name, num_lines)
+}
+
+// the name of a spin by any other block shown to the player
+fn spin_name(rtype: RustominoType, num_lines: usize) -> String {
+    with_lines_cleared(&format!("{:?}-Spin", rtype), num_lines)
+}
+
+fn with_lines_cleared(name: &str, num_lines: usize) -> String {
+    match num_lines {
+        1 => format!("{name} Single"),
+        2 => format!("{name} Double"),
+        3 => format!("{name} Triple"),
+        _ => name.to_string(),
+    }
+}
+
+// checks to see if ALL of the slots a block locked in are still above the
+// playfield after the cleared lines below them have moved them down,
+// slots on the cleared lines are gone so they aren't counted
+fn locked_above_playfield(slots: &[IVec2; 4], cleared_lines: &[usize]) -> bool {
+    slots
+        .iter()
+        .filter(|slot| !cleared_lines.contains(&(slot[1] as usize)))
+        .map(|slot| {
+            let cleared_below = cleared_lines
+                .iter()
+                .filter(|&&line| (line as i32) < slot[1])
+                .count();
+            slot[1] - cleared_below as i32
+        })
+        .min()
+        .is_some_and(|lowest| lowest >= PLAYFIELD_SIZE[1])
+}
+
+// moves a value towards the target by up to step without overshooting it
+fn step_towards(value: f32, target: f32, step: f32) -> f32 {
+    if target > value {
+        (value + step).min(target)
+    } else {
+        (value - step).max(target)
+    }
+}
+
+// steps a setting's value up or down
+fn adjusted(value: f64, step: f64, increase: bool) -> f64 {
+    if increase {
+        value + step
+    } else {
+        value - step
     }
-    true
+}
+
+/// total lines that need to be cleared to advance past a level
+/// every LINES_PER_LEVEL lines advances one level from the starting level
+fn next_level_lines(level: usize) -> usize {
+    (level + 1 - STARTING_LEVEL) * LINES_PER_LEVEL
 }
 
 /// calculate the gravity delay for the provided level
+/// using the selected gravity curve
 /// returns fractional seconds
-fn gravity_delay(level: usize) -> f64 {
-    let gravity_delay =
-        ((GRAVITY_NUMERATOR / (level as f64 + 0.001)).log(E) * GRAVITY_FACTOR + 0.3).max(0.001);
+/// every curve is non-increasing as the level rises, so a higher level is never
+/// slower, and stays above zero by bottoming out at 0.001s per row
+fn gravity_delay(level: usize, curve: GravityCurve) -> f64 {
+    let gravity_delay = match curve {
+        GravityCurve::Logarithmic => logarithmic_gravity_delay(level),
+        GravityCurve::Guideline => guideline_gravity_delay(level),
+    };
     log::info!("new gravity_delay {}", gravity_delay);
     gravity_delay
 }
+
+/// the original blocks gravity curve
+/// drops below 0.03s per row around level 15 and
+/// bottoms out at 0.001s per row (effectively instant) from level 20
+fn logarithmic_gravity_delay(level: usize) -> f64 {
+    ((GRAVITY_NUMERATOR / (level as f64 + 0.001)).log(E) * GRAVITY_FACTOR + 0.3).max(0.001)
+}
+
+/// the Tetris Guideline gravity curve (0.8 - (level - 1) * 0.007)^(level - 1)
+/// starts at 1s per row, drops below 0.01s per row around level 15 and
+/// bottoms out at 0.001s per row (effectively instant) from level 19
+fn guideline_gravity_delay(level: usize) -> f64 {
+    let level = level.max(1) as f64;
+    (0.8 - (level - 1.0) * 0.007)
+        .max(0.0)
+        .powf(level - 1.0)
+        .max(0.001)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_SEED: u64 = 42; // any fixed seed, so a failure can be reproduced
+
+    // a game playing a repeating sequence of blocks with the first one spawned
+    fn playing(script: Vec<RustominoType>) -> BlocksState {
+        let mut state =
+            BlocksState::without_assets(Settings::default(), draw::VIEW_WIDTH, draw::VIEW_HEIGHT);
+        state.rustomino_bag = RustominoBag::scripted(script);
+        state.state = GameState::Playing;
+        assert!(state.ready_playfield());
+        state
+    }
+
+    fn active_type(state: &BlocksState) -> Option<RustominoType> {
+        state.playfield.active_rustomino.as_ref().map(|r| r.rtype)
+    }
+
+    fn held_type(state: &BlocksState) -> Option<RustominoType> {
+        state.held_rustomino.as_ref().map(|r| r.rtype)
+    }
+
+    fn next_type(state: &BlocksState) -> Option<RustominoType> {
+        state.next_rustomino.as_ref().map(|r| r.rtype)
+    }
+
+    #[test]
+    fn first_hold_takes_the_next_block() {
+        let mut state = playing(vec![RustominoType::S, RustominoType::Z, RustominoType::T]);
+        assert_eq!(active_type(&state), Some(RustominoType::S));
+        assert_eq!(next_type(&state), Some(RustominoType::Z));
+
+        state.hold();
+        assert_eq!(held_type(&state), Some(RustominoType::S));
+        assert_eq!(active_type(&state), Some(RustominoType::Z));
+        assert_eq!(next_type(&state), Some(RustominoType::T));
+        assert!(state.hold_used);
+    }
+
+    #[test]
+    fn hold_swaps_with_the_held_block() {
+        let mut state = playing(vec![RustominoType::S, RustominoType::Z, RustominoType::T]);
+        state.hold();
+        state.hard_drop();
+        assert!(state.ready_playfield());
+        assert_eq!(active_type(&state), Some(RustominoType::T));
+
+        state.hold();
+        assert_eq!(held_type(&state), Some(RustominoType::T));
+        assert_eq!(active_type(&state), Some(RustominoType::S));
+        // swapping doesn't draw from the bag
+        assert_eq!(next_type(&state), Some(RustominoType::S));
+    }
+
+    #[test]
+    fn hold_is_blocked_after_one_use() {
+        let mut state = playing(vec![RustominoType::S, RustominoType::Z, RustominoType::T]);
+        state.hold();
+        state.hold();
+        assert_eq!(held_type(&state), Some(RustominoType::S));
+        assert_eq!(active_type(&state), Some(RustominoType::Z));
+        assert_eq!(next_type(&state), Some(RustominoType::T));
+    }
+
+    #[test]
+    fn hold_is_available_again_after_lock() {
+        let mut state = playing(vec![RustominoType::S, RustominoType::Z, RustominoType::T]);
+        state.hold();
+        assert!(!state.hold_available());
+        state.hard_drop();
+        assert!(!state.hold_used);
+        assert!(state.hold_available());
+
+        assert!(state.ready_playfield());
+        state.hold();
+        assert_eq!(held_type(&state), Some(RustominoType::T));
+    }
+
+    #[test]
+    fn game_over_menu_retries_the_same_seed() {
+        let mut state = playing(vec![RustominoType::S]);
+        state.rustomino_bag = RustominoBag::with_seed(TEST_SEED);
+        state.game_over(GameOverReason::BlockOut);
+        assert_eq!(
+            state.game_over_menu.selected_item(),
+            GameOverItem::PlayAgain
+        );
+
+        state.game_over_action(MenuAction::Down);
+        assert_eq!(
+            state.game_over_menu.selected_item(),
+            GameOverItem::RetrySeed
+        );
+        state.game_over_action(MenuAction::Select);
+        assert_eq!(state.rustomino_bag.seed(), TEST_SEED);
+        assert_eq!(state.state, GameState::Playing);
+        assert!(state.countdown.is_some());
+    }
+
+    #[test]
+    fn game_over_menu_quits() {
+        let mut state = playing(vec![RustominoType::S]);
+        state.game_over(GameOverReason::BlockOut);
+        state.game_over_action(MenuAction::Up);
+        assert_eq!(state.game_over_menu.selected_item(), GameOverItem::Quit);
+        state.game_over_action(MenuAction::Select);
+        assert_eq!(state.state, GameState::Quit);
+    }
+
+    #[test]
+    fn snapshot_records_the_mode_and_draws() {
+        let mut state = playing(vec![RustominoType::S, RustominoType::Z, RustominoType::T]);
+        state.mode = GameMode::Trainer(RustominoType::T);
+        let snapshot = state.snapshot();
+        assert!(snapshot.contains("mode: Trainer(T)\n"), "{}", snapshot);
+        // the active and next blocks
+        assert!(snapshot.contains("drawn: 2\n"), "{}", snapshot);
+        assert!(snapshot.contains("upcoming: [T, S, Z, T,"), "{}", snapshot);
+    }
+
+    #[test]
+    fn hard_drop_after_hold_locks_the_swapped_in_block() {
+        let mut state = playing(vec![RustominoType::S, RustominoType::Z, RustominoType::T]);
+        state.hold();
+        state.hard_drop();
+
+        let floor = state.playfield.slots[0];
+        assert_eq!(
+            floor
+                .iter()
+                .filter(|slot| **slot == SlotState::Locked(RustominoType::Z))
+                .count(),
+            2
+        );
+        assert!(state
+            .playfield
+            .slots
+            .iter()
+            .flatten()
+            .all(|slot| *slot != SlotState::Locked(RustominoType::S)));
+        assert_eq!(held_type(&state), Some(RustominoType::S));
+    }
+
+    #[test]
+    fn ten_lines_advance_a_level() {
+        let mut state = playing(vec![RustominoType::I]);
+        assert_eq!(state.level, STARTING_LEVEL);
+
+        for cleared in 1..=LINES_PER_LEVEL {
+            // fill the floor around where the I lands so dropping it clears one line
+            let landing = state.playfield.active_landing_slots().unwrap();
+            for (x, slot) in state.playfield.slots[0].iter_mut().enumerate() {
+                if !landing.contains(&IVec2::new(x as i32, 0)) {
+                    *slot = SlotState::Garbage;
+                }
+            }
+            state.hard_drop();
+            assert_eq!(state.total_lines_cleared, cleared);
+            assert!(state.ready_playfield());
+
+            let (progress, needed) = state.level_progress();
+            assert_eq!(
+                needed,
+                next_level_lines(state.level) - next_level_lines(state.level - 1)
+            );
+            assert_eq!(progress, cleared % LINES_PER_LEVEL);
+        }
+        assert_eq!(state.level, STARTING_LEVEL + 1);
+    }
+
+    // soft drops the active block until it can't move and checks it's in lockdown
+    // without the landing using up a lockdown reset
+    fn soft_drop_to_rest(state: &mut BlocksState) {
+        state.lockdown_resets = 2;
+        while state.playfield.active_can_fall() {
+            state.soft_drop();
+        }
+        assert!(matches!(
+            state.playfield.get_active_state(),
+            Some(RustominoState::Falling { .. })
+        ));
+
+        state.soft_drop();
+        assert!(matches!(
+            state.playfield.get_active_state(),
+            Some(RustominoState::Lockdown { .. })
+        ));
+        assert_eq!(state.lockdown_resets, 2);
+
+        // pushing down again leaves the lockdown running
+        state.soft_drop();
+        assert!(state.playfield.active_rustomino.is_some());
+        assert_eq!(state.lockdown_resets, 2);
+    }
+
+    #[test]
+    fn soft_drop_onto_the_floor() {
+        let mut state = playing(vec![RustominoType::T]);
+        soft_drop_to_rest(&mut state);
+        let slots = state
+            .playfield
+            .active_rustomino
+            .as_ref()
+            .unwrap()
+            .playfield_slots();
+        assert_eq!(slots.iter().map(|b| b.y).min(), Some(0));
+    }
+
+    #[test]
+    fn soft_drop_onto_the_stack() {
+        let mut state = playing(vec![RustominoType::T]);
+        for row in state.playfield.slots.iter_mut().take(4) {
+            for slot in row.iter_mut().skip(1) {
+                *slot = SlotState::Garbage;
+            }
+        }
+        soft_drop_to_rest(&mut state);
+        let slots = state
+            .playfield
+            .active_rustomino
+            .as_ref()
+            .unwrap()
+            .playfield_slots();
+        assert_eq!(slots.iter().map(|b| b.y).min(), Some(4));
+    }
+
+    #[test]
+    fn lock_on_contact_locks_on_the_next_gravity_tick() {
+        let mut state = playing(vec![RustominoType::T]);
+        state.settings.lock_on_contact = true;
+        state.spawn_delay = 0.0;
+        while state.playfield.translate_active(TranslationDirection::Down) {}
+        assert!(state.playfield.active_rustomino.is_some());
+
+        state.playing_update(state.gravity_delay);
+        assert!(state.playfield.active_rustomino.is_none());
+        assert!(state.playfield.slots[0].contains(&SlotState::Locked(RustominoType::T)));
+    }
+
+    #[test]
+    fn gravity_never_slows_as_the_level_rises() {
+        for curve in GravityCurve::iter() {
+            for level in 1..100 {
+                let delay = gravity_delay(level, curve);
+                let next = gravity_delay(level + 1, curve);
+                assert!(next <= delay, "{:?} level {}", curve, level);
+                assert!(next > 0.0, "{:?} level {}", curve, level);
+            }
+        }
+    }
+
+    #[test]
+    fn locked_above_playfield_cases() {
+        let top = PLAYFIELD_SIZE[1];
+        let flat = [
+            IVec2::new(3, top),
+            IVec2::new(4, top),
+            IVec2::new(5, top),
+            IVec2::new(4, top + 1),
+        ];
+        assert!(locked_above_playfield(&flat, &[]));
+        // a line cleared anywhere below brings it into view
+        assert!(!locked_above_playfield(&flat, &[0]));
+        // the bottom row is cleared but the rest of the block is still above
+        assert!(locked_above_playfield(&flat, &[top as usize]));
+        // nothing is left when both its rows are cleared
+        assert!(!locked_above_playfield(
+            &flat,
+            &[top as usize, top as usize + 1]
+        ));
+
+        let straddling = [
+            IVec2::new(4, top - 1),
+            IVec2::new(4, top),
+            IVec2::new(4, top + 1),
+            IVec2::new(5, top),
+        ];
+        assert!(!locked_above_playfield(&straddling, &[]));
+        assert!(!locked_above_playfield(&straddling, &[top as usize - 1]));
+    }
+
+    // a game with the visible playfield stacked to the top and a T
+    // spawned in the buffer rows above it
+    fn stacked_to_the_top() -> BlocksState {
+        let mut state = playing(vec![RustominoType::T]);
+        for row in state
+            .playfield
+            .slots
+            .iter_mut()
+            .take(PLAYFIELD_SIZE[1] as usize)
+        {
+            for slot in row.iter_mut().skip(1) {
+                *slot = SlotState::Garbage;
+            }
+        }
+        assert_eq!(state.playfield.active_drop_distance(), 0);
+        state
+    }
+
+    #[test]
+    fn locking_above_the_playfield_ends_the_game() {
+        let mut state = stacked_to_the_top();
+        state.hard_drop();
+        assert_eq!(state.state, GameState::GameOver);
+        assert_eq!(state.game_over_reason, Some(GameOverReason::LockOut));
+    }
+
+    #[test]
+    fn clearing_a_line_under_a_buffer_row_lock_continues_play() {
+        let mut state = stacked_to_the_top();
+        // a complete line waiting below the block is cleared when it locks
+        state.playfield.slots[PLAYFIELD_SIZE[1] as usize - 1][0] = SlotState::Garbage;
+        state.hard_drop();
+        assert_eq!(state.total_lines_cleared, 1);
+        assert_eq!(state.state, GameState::Playing);
+        assert_eq!(state.game_over_reason, None);
+    }
+
+    #[test]
+    fn block_controls_do_nothing_without_an_active_block() {
+        let mut state = playing(vec![RustominoType::S, RustominoType::Z, RustominoType::T]);
+        state.hard_drop();
+        assert!(state.playfield.active_rustomino.is_none());
+        let slots = state.playfield.slots;
+        let score = state.score;
+
+        for control in Control::iter().filter(|c| c.moves_block()) {
+            state.handle_control(control);
+            assert!(state.playfield.active_rustomino.is_none(), "{:?}", control);
+            assert_eq!(state.playfield.slots, slots, "{:?}", control);
+        }
+        assert_eq!(state.score, score);
+        assert_eq!(held_type(&state), None);
+        assert!(state.pending_rotation.is_none());
+        assert_eq!(state.state, GameState::Playing);
+    }
+}
@@ -0,0 +1,79 @@
+use std::path::Path;
+
+const GARBAGE_SCHEDULE_FILE: &str = "garbage.txt";
+
+/// rows of garbage to add once the play clock reaches a time
+#[derive(Debug, Clone, Copy)]
+struct GarbageEvent {
+    time: f64,
+    rows: usize,
+}
+
+/// a timeline of garbage pushes for practicing under pressure
+///
+/// loaded from `garbage.txt` in the user's data directory,
+/// one push per line as `<seconds> <rows>`, e.g. `5 2` adds two rows at 5s
+/// lines starting with `#` are ignored
+#[derive(Debug, Clone)]
+pub struct GarbageSchedule {
+    events: Vec<GarbageEvent>,
+    next: usize, // index of the next event to apply
+}
+
+impl GarbageSchedule {
+    /// returns None if there's no schedule file or it has no valid pushes
+    pub fn load(dir: &Path) -> Option<GarbageSchedule> {
+        let path = dir.join(GARBAGE_SCHEDULE_FILE);
+        let contents = std::fs::read_to_string(&path).ok()?;
+
+        let mut events = Vec::new();
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match parse_event(line) {
+                Some(event) => events.push(event),
+                None => log::warn!("ignoring invalid garbage push: {}", line),
+            }
+        }
+        if events.is_empty() {
+            return None;
+        }
+        events.sort_by(|a, b| a.time.total_cmp(&b.time));
+        log::info!("loaded {} garbage pushes from {:?}", events.len(), path);
+        Some(GarbageSchedule { events, next: 0 })
+    }
+
+    /// start the schedule over for a new game
+    pub fn reset(&mut self) {
+        self.next = 0;
+    }
+
+    /// returns the number of rows due by the play clock time
+    /// that haven't been added yet
+    pub fn due(&mut self, play_time: f64) -> usize {
+        let mut rows = 0;
+        while let Some(event) = self.events.get(self.next) {
+            if event.time > play_time {
+                break;
+            }
+            rows += event.rows;
+            self.next += 1;
+        }
+        rows
+    }
+}
+
+fn parse_event(line: &str) -> Option<GarbageEvent> {
+    let mut parts = line.split_whitespace();
+    let time = parts.next()?.trim_end_matches('s').parse::<f64>().ok()?;
+    let rows = parts
+        .next()?
+        .trim_start_matches('+')
+        .parse::<usize>()
+        .ok()?;
+    if parts.next().is_some() || time < 0.0 {
+        return None;
+    }
+    Some(GarbageEvent { time, rows })
+}
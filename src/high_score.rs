@@ -0,0 +1,31 @@
+use std::path::Path;
+
+const HIGH_SCORE_FILE: &str = "high_score.txt";
+
+/// loads the best marathon score from the user's data directory
+/// returns 0 if no score has been saved yet or the file is invalid
+pub fn load(dir: &Path) -> usize {
+    let path = dir.join(HIGH_SCORE_FILE);
+    match std::fs::read_to_string(&path) {
+        Ok(contents) => match contents.trim().parse() {
+            Ok(score) => score,
+            Err(e) => {
+                log::warn!("ignoring invalid high score in {:?}: {}", path, e);
+                0
+            }
+        },
+        Err(e) => {
+            log::info!("unable to read high score from {:?}: {}", path, e);
+            0
+        }
+    }
+}
+
+/// saves a new best marathon score to the user's data directory
+pub fn save(dir: &Path, score: usize) {
+    let path = dir.join(HIGH_SCORE_FILE);
+    match std::fs::create_dir_all(dir).and_then(|_| std::fs::write(&path, score.to_string())) {
+        Ok(_) => log::info!("saved high score {} to {:?}", score, path),
+        Err(e) => log::warn!("unable to save high score to {:?}: {}", path, e),
+    }
+}
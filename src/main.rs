@@ -3,12 +3,24 @@ use std::{env, path};
 
 use ggez::{conf, event, ContextBuilder};
 
+mod board_code;
 mod controls;
+mod demo;
 mod draw;
 mod game;
+mod garbage;
+mod high_score;
 mod menus;
 mod playfield;
+mod popups;
+mod profile;
+mod records;
 mod rustomino;
+mod savegame;
+mod scenario;
+mod scoring;
+mod settings;
+mod theme;
 mod util;
 
 const ASSETS_FOLDER: &str = "./resources";
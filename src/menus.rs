@@ -1,14 +1,10 @@
+use std::ops::Range;
+
 use ggez::graphics;
 use once_cell::sync::Lazy;
+use strum::{EnumIter, IntoEnumIterator};
 
-static MENU_ENTRIES: Lazy<Vec<String>> = Lazy::new(|| {
-    let entries = vec![
-        "Start Game".to_string(),
-        "Options".to_string(),
-        "Quit Game".to_string(),
-    ];
-    entries
-});
+use crate::settings::{LastMode, Settings};
 
 static PAUSED_ENTRIES: Lazy<Vec<String>> = Lazy::new(|| {
     let entries = vec![
@@ -20,6 +16,11 @@ static PAUSED_ENTRIES: Lazy<Vec<String>> = Lazy::new(|| {
     entries
 });
 
+static CONFIRM_QUIT_ENTRIES: Lazy<Vec<String>> = Lazy::new(|| {
+    let entries = vec!["Keep Playing".to_string(), "Quit Game".to_string()];
+    entries
+});
+
 pub trait Menu {
     fn items(&self) -> &Vec<graphics::Text>;
     fn selected(&self) -> usize;
@@ -27,20 +28,88 @@ pub trait Menu {
     fn previous(&mut self);
     fn reset_selection(&mut self);
     fn set_selection(&mut self, index: usize);
+
+    /// the range of items to draw when only `visible` items fit
+    /// scrolled so the selected item stays in view
+    fn visible_items(&self, visible: usize) -> Range<usize> {
+        let len = self.items().len();
+        if len <= visible {
+            return 0..len;
+        }
+        let start = self
+            .selected()
+            .saturating_sub(visible / 2)
+            .min(len - visible);
+        start..start + visible
+    }
+}
+
+/// the main menu entries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuItem {
+    Continue, // only shown when there's a saved game
+    StartGame,
+    Practice,
+    WatchDemo,
+    Options,
+    Quit,
+}
+
+impl MenuItem {
+    fn name(&self) -> &'static str {
+        match self {
+            MenuItem::Continue => "Continue",
+            MenuItem::StartGame => "Start Game",
+            MenuItem::Practice => "Practice",
+            MenuItem::WatchDemo => "Watch Demo",
+            MenuItem::Options => "Options",
+            MenuItem::Quit => "Quit Game",
+        }
+    }
 }
 
 pub struct MenuState {
+    items: Vec<MenuItem>,
     menu: Vec<graphics::Text>,
     selected: usize,
 }
 
 impl MenuState {
-    pub fn new() -> Self {
-        let menu = MENU_ENTRIES
+    pub fn new(can_continue: bool) -> Self {
+        let mut items = vec![
+            MenuItem::StartGame,
+            MenuItem::Practice,
+            MenuItem::WatchDemo,
+            MenuItem::Options,
+            MenuItem::Quit,
+        ];
+        if can_continue {
+            items.insert(0, MenuItem::Continue);
+        }
+        let menu = items
             .iter()
-            .map(graphics::Text::new)
+            .map(|item| graphics::Text::new(item.name()))
             .collect::<Vec<graphics::Text>>();
-        MenuState { menu, selected: 0 }
+        MenuState {
+            items,
+            menu,
+            selected: 0,
+        }
+    }
+
+    pub fn selected_item(&self) -> MenuItem {
+        self.items[self.selected]
+    }
+
+    /// highlight the entry that starts the provided mode
+    pub fn select_mode(&mut self, mode: LastMode) {
+        let item = match mode {
+            LastMode::Marathon => MenuItem::StartGame,
+            LastMode::Demo => MenuItem::WatchDemo,
+        };
+        if let Some(index) = self.items.iter().position(|i| *i == item) {
+            self.selected = index;
+        }
     }
 }
 
@@ -110,3 +179,428 @@ impl Menu for PausedState {
         self.selected = index;
     }
 }
+
+pub struct ConfirmQuitState {
+    menu: Vec<graphics::Text>,
+    selected: usize,
+}
+
+impl ConfirmQuitState {
+    pub fn new() -> Self {
+        let menu = CONFIRM_QUIT_ENTRIES
+            .iter()
+            .map(graphics::Text::new)
+            .collect::<Vec<graphics::Text>>();
+        // staying is selected so a second accidental press doesn't quit
+        ConfirmQuitState { menu, selected: 0 }
+    }
+
+    pub fn quit_selected(&self) -> bool {
+        self.selected == 1
+    }
+}
+
+impl Menu for ConfirmQuitState {
+    fn items(&self) -> &Vec<graphics::Text> {
+        &self.menu
+    }
+    fn next(&mut self) {
+        self.selected = (self.selected + 1) % self.menu.len();
+    }
+    fn selected(&self) -> usize {
+        self.selected
+    }
+    fn previous(&mut self) {
+        self.selected = if self.selected == 0 {
+            self.menu.len() - 1
+        } else {
+            self.selected - 1
+        };
+    }
+    fn reset_selection(&mut self) {
+        self.selected = 0;
+    }
+    fn set_selection(&mut self, index: usize) {
+        assert!(index < self.menu.len());
+        self.selected = index;
+    }
+}
+
+/// the game over screen entries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameOverItem {
+    PlayAgain,
+    RetrySeed, // only shown when the game was dealt from a seed that can be replayed
+    Menu,
+    Quit,
+}
+
+impl GameOverItem {
+    fn name(&self) -> &'static str {
+        match self {
+            GameOverItem::PlayAgain => "Play Again",
+            GameOverItem::RetrySeed => "Retry Same Seed",
+            GameOverItem::Menu => "Exit to Menu",
+            GameOverItem::Quit => "Quit Game",
+        }
+    }
+}
+
+pub struct GameOverState {
+    items: Vec<GameOverItem>,
+    menu: Vec<graphics::Text>,
+    selected: usize,
+}
+
+impl GameOverState {
+    pub fn new(can_retry: bool) -> Self {
+        let mut items = vec![
+            GameOverItem::PlayAgain,
+            GameOverItem::Menu,
+            GameOverItem::Quit,
+        ];
+        if can_retry {
+            items.insert(1, GameOverItem::RetrySeed);
+        }
+        let menu = items
+            .iter()
+            .map(|item| graphics::Text::new(item.name()))
+            .collect::<Vec<graphics::Text>>();
+        GameOverState {
+            items,
+            menu,
+            selected: 0,
+        }
+    }
+
+    pub fn selected_item(&self) -> GameOverItem {
+        self.items[self.selected]
+    }
+}
+
+impl Menu for GameOverState {
+    fn items(&self) -> &Vec<graphics::Text> {
+        &self.menu
+    }
+    fn next(&mut self) {
+        self.selected = (self.selected + 1) % self.menu.len();
+    }
+    fn selected(&self) -> usize {
+        self.selected
+    }
+    fn previous(&mut self) {
+        self.selected = if self.selected == 0 {
+            self.menu.len() - 1
+        } else {
+            self.selected - 1
+        };
+    }
+    fn reset_selection(&mut self) {
+        self.selected = 0;
+    }
+    fn set_selection(&mut self, index: usize) {
+        assert!(index < self.menu.len());
+        self.selected = index;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter)]
+pub enum OptionsItem {
+    Profile,
+    MusicVolume,
+    LockdownDelay,
+    LockdownMaxResets,
+    LockdownReset,
+    MaxGroundTime,
+    SpawnDelay,
+    ReduceMotion,
+    GravityCurve,
+    SpeedCap,
+    Scoring,
+    AllSpin,
+    ShowBufferRows,
+    HideStaging,
+    InfiniteHold,
+    HoldLimit,
+    HoldLimitScope,
+    HardDropLock,
+    LockOnContact,
+    SpawnFlatUp,
+    DasCarry,
+    StickyRotation,
+    LockPitch,
+    DangerRows,
+    DangerSound,
+    ResetsSound,
+    Metronome,
+    DropHint,
+    SpawnGuide,
+    Drought,
+    LockTimer,
+    Heatmap,
+    GhostColor,
+    MonoBlocks,
+    Frame,
+    Theme,
+    Background,
+    HudScale,
+    SoftDropSpeed,
+    Das,
+    Arr,
+    SoftDropDas,
+    SoftDropArr,
+    KeyLayout,
+    SwapDropKeys,
+    Trainer,
+    ResetRecords,
+    ConfirmQuit,
+}
+
+impl OptionsItem {
+    fn name(&self) -> &'static str {
+        match self {
+            OptionsItem::Profile => "Profile",
+            OptionsItem::MusicVolume => "Music Volume",
+            OptionsItem::LockdownDelay => "Lock Delay",
+            OptionsItem::LockdownMaxResets => "Lock Resets",
+            OptionsItem::LockdownReset => "Reset Mode",
+            OptionsItem::MaxGroundTime => "Ground Limit",
+            OptionsItem::SpawnDelay => "Spawn Delay",
+            OptionsItem::ReduceMotion => "Reduce Motion",
+            OptionsItem::GravityCurve => "Gravity",
+            OptionsItem::SpeedCap => "Speed Cap",
+            OptionsItem::Scoring => "Scoring",
+            OptionsItem::AllSpin => "All Spin",
+            OptionsItem::ShowBufferRows => "Buffer Rows",
+            OptionsItem::HideStaging => "Hide Staging",
+            OptionsItem::InfiniteHold => "Infinite Hold",
+            OptionsItem::HoldLimit => "Hold Limit",
+            OptionsItem::HoldLimitScope => "Hold Limit Scope",
+            OptionsItem::HardDropLock => "Hard Drop Lock",
+            OptionsItem::LockOnContact => "Lock On Contact",
+            OptionsItem::SpawnFlatUp => "Spawn Flat",
+            OptionsItem::DasCarry => "DAS Carry",
+            OptionsItem::StickyRotation => "Sticky Rotate",
+            OptionsItem::LockPitch => "Lock Pitch",
+            OptionsItem::DangerRows => "Danger Warning",
+            OptionsItem::DangerSound => "Danger Sound",
+            OptionsItem::ResetsSound => "Out of Resets",
+            OptionsItem::Metronome => "Metronome",
+            OptionsItem::DropHint => "Drop Hint",
+            OptionsItem::SpawnGuide => "Spawn Guide",
+            OptionsItem::Drought => "I Drought",
+            OptionsItem::LockTimer => "Lock Timer",
+            OptionsItem::Heatmap => "Heatmap",
+            OptionsItem::GhostColor => "Ghost",
+            OptionsItem::MonoBlocks => "Mono Blocks",
+            OptionsItem::Frame => "Frame",
+            OptionsItem::Theme => "Theme",
+            OptionsItem::Background => "Background",
+            OptionsItem::HudScale => "HUD Size",
+            OptionsItem::SoftDropSpeed => "Soft Drop",
+            OptionsItem::Das => "DAS",
+            OptionsItem::Arr => "ARR",
+            OptionsItem::SoftDropDas => "Soft Drop DAS",
+            OptionsItem::SoftDropArr => "Soft Drop ARR",
+            OptionsItem::KeyLayout => "Keys",
+            OptionsItem::SwapDropKeys => "Swap Drops",
+            OptionsItem::Trainer => "Trainer",
+            OptionsItem::ResetRecords => "Reset Records",
+            OptionsItem::ConfirmQuit => "Confirm Quit",
+        }
+    }
+
+    fn value(&self, settings: &Settings, music_volume: f32, profile: &str) -> String {
+        match self {
+            OptionsItem::Profile => profile.to_string(),
+            OptionsItem::MusicVolume => format!("{:.0}%", music_volume * 100.0),
+            OptionsItem::LockdownDelay => format!("{:.2}s", settings.lockdown_delay),
+            OptionsItem::LockdownMaxResets => settings.lockdown_max_resets.to_string(),
+            OptionsItem::MaxGroundTime if settings.max_ground_time <= 0.0 => "Off".to_string(),
+            OptionsItem::MaxGroundTime => format!("{:.0}s", settings.max_ground_time),
+            OptionsItem::SpawnDelay if settings.spawn_delay <= 0.0 => "Off".to_string(),
+            OptionsItem::SpawnDelay => millis(settings.spawn_delay),
+            OptionsItem::ReduceMotion => on_off(settings.reduce_motion).to_string(),
+            OptionsItem::LockdownReset => settings.lockdown_reset.name().to_string(),
+            OptionsItem::GravityCurve => settings.gravity_curve.name().to_string(),
+            OptionsItem::SpeedCap if settings.speed_cap == 0 => "Off".to_string(),
+            OptionsItem::SpeedCap => format!("Level {}", settings.speed_cap),
+            OptionsItem::Scoring => settings.scoring.name().to_string(),
+            OptionsItem::AllSpin => on_off(settings.all_spin).to_string(),
+            OptionsItem::ShowBufferRows => on_off(settings.show_buffer_rows).to_string(),
+            OptionsItem::HideStaging => on_off(settings.hide_staging).to_string(),
+            OptionsItem::InfiniteHold => on_off(settings.infinite_hold).to_string(),
+            OptionsItem::HoldLimit if settings.hold_limit == 0 => "Off".to_string(),
+            OptionsItem::HoldLimit => settings.hold_limit.to_string(),
+            OptionsItem::HoldLimitScope => settings.hold_limit_scope.name().to_string(),
+            OptionsItem::HardDropLock => on_off(settings.hard_drop_lock).to_string(),
+            OptionsItem::LockOnContact => on_off(settings.lock_on_contact).to_string(),
+            OptionsItem::SpawnFlatUp if settings.spawn_flat_up => "Up".to_string(),
+            OptionsItem::SpawnFlatUp => "Down".to_string(),
+            OptionsItem::DasCarry => on_off(settings.das_carry).to_string(),
+            OptionsItem::StickyRotation => on_off(settings.sticky_rotation).to_string(),
+            OptionsItem::DangerRows if settings.danger_rows == 0 => "Off".to_string(),
+            OptionsItem::DangerRows => format!("{} Rows", settings.danger_rows),
+            OptionsItem::DangerSound => on_off(settings.danger_sound).to_string(),
+            OptionsItem::ResetsSound => on_off(settings.resets_exhausted_sound).to_string(),
+            OptionsItem::Metronome if settings.metronome_bpm == 0 => "Off".to_string(),
+            OptionsItem::Metronome => format!("{} BPM", settings.metronome_bpm),
+            OptionsItem::LockPitch => on_off(settings.lock_pitch_variation).to_string(),
+            OptionsItem::DropHint => on_off(settings.show_drop_hint).to_string(),
+            OptionsItem::SpawnGuide => on_off(settings.show_spawn_guide).to_string(),
+            OptionsItem::Drought => on_off(settings.show_drought).to_string(),
+            OptionsItem::LockTimer => on_off(settings.show_lock_timer).to_string(),
+            OptionsItem::Heatmap => on_off(settings.show_heatmap).to_string(),
+            OptionsItem::Frame => on_off(settings.show_frame).to_string(),
+            OptionsItem::GhostColor if settings.colored_ghost => "Colored".to_string(),
+            OptionsItem::GhostColor => "Gray".to_string(),
+            OptionsItem::MonoBlocks => on_off(settings.mono_blocks).to_string(),
+            OptionsItem::Theme => settings.theme.name().to_string(),
+            OptionsItem::Background => settings.background_color.name().to_string(),
+            OptionsItem::HudScale => settings.hud_scale.name().to_string(),
+            OptionsItem::SoftDropSpeed => settings.soft_drop_speed.name().to_string(),
+            OptionsItem::Das => millis(settings.das),
+            OptionsItem::Arr => millis(settings.arr),
+            OptionsItem::SoftDropDas => millis(settings.soft_drop_das),
+            OptionsItem::SoftDropArr => millis(settings.soft_drop_arr),
+            OptionsItem::KeyLayout => settings.key_layout.name().to_string(),
+            OptionsItem::SwapDropKeys => on_off(settings.swap_drop_keys).to_string(),
+            OptionsItem::Trainer => match settings.trainer_block {
+                Some(rtype) => format!("{:?} Only", rtype),
+                None => "Off".to_string(),
+            },
+            OptionsItem::ConfirmQuit => on_off(settings.confirm_quit).to_string(),
+            OptionsItem::ResetRecords => "Select".to_string(),
+        }
+    }
+}
+
+fn millis(seconds: f64) -> String {
+    format!("{:.0}ms", seconds * 1000.0)
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value {
+        "On"
+    } else {
+        "Off"
+    }
+}
+
+pub struct OptionsState {
+    menu: Vec<graphics::Text>,
+    values: Vec<graphics::Text>,
+    selected: usize,
+}
+
+impl OptionsState {
+    pub fn new(settings: &Settings, music_volume: f32, profile: &str) -> Self {
+        let mut options_state = OptionsState {
+            menu: Vec::new(),
+            values: Vec::new(),
+            selected: 0,
+        };
+        options_state.update(settings, music_volume, profile);
+        options_state
+    }
+
+    /// rebuild the menu text from the current setting values
+    pub fn update(&mut self, settings: &Settings, music_volume: f32, profile: &str) {
+        self.menu = OptionsItem::iter()
+            .map(|item| graphics::Text::new(item.name()))
+            .collect::<Vec<graphics::Text>>();
+        self.values = OptionsItem::iter()
+            .map(|item| graphics::Text::new(item.value(settings, music_volume, profile)))
+            .collect::<Vec<graphics::Text>>();
+    }
+
+    /// the current value of each item, drawn beside its name
+    pub fn values(&self) -> &Vec<graphics::Text> {
+        &self.values
+    }
+
+    pub fn selected_item(&self) -> OptionsItem {
+        OptionsItem::iter()
+            .nth(self.selected)
+            .expect("selected option out of range")
+    }
+}
+
+impl Menu for OptionsState {
+    fn items(&self) -> &Vec<graphics::Text> {
+        &self.menu
+    }
+    fn next(&mut self) {
+        self.selected = (self.selected + 1) % self.menu.len();
+    }
+    fn selected(&self) -> usize {
+        self.selected
+    }
+    fn previous(&mut self) {
+        self.selected = if self.selected == 0 {
+            self.menu.len() - 1
+        } else {
+            self.selected - 1
+        };
+    }
+    fn reset_selection(&mut self) {
+        self.selected = 0;
+    }
+    fn set_selection(&mut self, index: usize) {
+        assert!(index < self.menu.len());
+        self.selected = index;
+    }
+}
+
+/// picks a scenario to practice from, the last entry goes back to the main menu
+pub struct ScenarioState {
+    names: Vec<String>,
+    menu: Vec<graphics::Text>,
+    selected: usize,
+}
+
+impl ScenarioState {
+    pub fn new(names: Vec<String>) -> Self {
+        let menu = names
+            .iter()
+            .map(graphics::Text::new)
+            .chain([graphics::Text::new("Back")])
+            .collect::<Vec<graphics::Text>>();
+        ScenarioState {
+            names,
+            menu,
+            selected: 0,
+        }
+    }
+
+    /// the name of the selected scenario, None when Back is selected
+    pub fn selected_scenario(&self) -> Option<&str> {
+        self.names.get(self.selected).map(String::as_str)
+    }
+}
+
+impl Menu for ScenarioState {
+    fn items(&self) -> &Vec<graphics::Text> {
+        &self.menu
+    }
+    fn next(&mut self) {
+        self.selected = (self.selected + 1) % self.menu.len();
+    }
+    fn selected(&self) -> usize {
+        self.selected
+    }
+    fn previous(&mut self) {
+        self.selected = if self.selected == 0 {
+            self.menu.len() - 1
+        } else {
+            self.selected - 1
+        };
+    }
+    fn reset_selection(&mut self) {
+        self.selected = 0;
+    }
+    fn set_selection(&mut self, index: usize) {
+        assert!(index < self.menu.len());
+        self.selected = index;
+    }
+}
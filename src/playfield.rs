@@ -1,21 +1,27 @@
 use ggez::glam::IVec2;
 
-use crate::{
-    rustomino::{translated, Rotation, Rustomino, RustominoState, RustominoType},
-    util::variants_equal,
-};
+use crate::rustomino::{translated, Direction, Rotation, Rustomino, RustominoState, RustominoType};
 use std::fmt::Display;
+use strum::IntoEnumIterator;
 
 pub const PLAYFIELD_SLOTS: [usize; 2] = [10, 22];
 pub const PLAYFIELD_SIZE: [i32; 2] = [10, 20];
 
-type PlayfieldSlots = [[SlotState; PLAYFIELD_SLOTS[0]]; PLAYFIELD_SLOTS[1]];
+// index of the wall kick test which upgrades a mini t-spin to a full t-spin
+const T_SPIN_UPGRADE_KICK: usize = 4;
+
+pub type PlayfieldSlots = [[SlotState; PLAYFIELD_SLOTS[0]]; PLAYFIELD_SLOTS[1]];
+/// how many times a block has been locked in each slot
+pub type LockCounts = [[u32; PLAYFIELD_SLOTS[0]]; PLAYFIELD_SLOTS[1]];
 
 #[derive(Debug)]
 pub struct Playfield {
     pub slots: PlayfieldSlots,
     pub active_rustomino: Option<Rustomino>,
     pub ghost_rustomino: Option<Rustomino>,
+    // the wall kick test used by the active rustomino's last movement
+    // None if the last movement wasn't a rotation
+    last_kick: Option<usize>,
 }
 
 impl Playfield {
@@ -25,6 +31,7 @@ impl Playfield {
             slots: [[SlotState::Empty; PLAYFIELD_SLOTS[0]]; PLAYFIELD_SLOTS[1]],
             active_rustomino: None,
             ghost_rustomino: None,
+            last_kick: None,
         }
     }
 
@@ -40,9 +47,9 @@ impl Playfield {
             &rustomino.playfield_slots(),
             SlotState::Occupied(rustomino.rtype),
         );
-        self.ghost_rustomino = Some(rustomino.clone());
         self.active_rustomino = Some(rustomino);
-        self.update_ghost_rustomino(false);
+        self.last_kick = None;
+        self.update_ghost_rustomino();
         ok
     }
 
@@ -56,7 +63,7 @@ impl Playfield {
             &active_rustomino.playfield_slots(),
             SlotState::Empty,
         );
-        self.update_ghost_rustomino(false);
+        self.update_ghost_rustomino();
         Some(active_rustomino.reset())
     }
     /// checks to see if the playfield needs the next rustomino
@@ -102,7 +109,9 @@ impl Playfield {
         };
 
         // check to see if the block can be rotated with or without a wall kick
-        let Some(wall_kick_trans) = check_rotation(&self.slots, active_rustomino, &rotation) else {
+        let Some((kick, wall_kick_trans)) =
+            check_rotation(&self.slots, active_rustomino, &rotation)
+        else {
             return false;
         };
 
@@ -115,6 +124,7 @@ impl Playfield {
 
         // perform the translation
         active_rustomino.rotate(&rotation, &wall_kick_trans);
+        self.last_kick = Some(kick);
 
         // set the new slot states to occupied
         set_playfield_slot_states(
@@ -123,11 +133,28 @@ impl Playfield {
             SlotState::Occupied(active_rustomino.rtype),
         );
 
-        self.update_ghost_rustomino(true);
+        self.update_ghost_rustomino();
 
         true
     }
 
+    /// Attempt to turn the active rustomino halfway around
+    pub fn rotate_active_180(&mut self) -> bool {
+        // there are no 180 wall kicks, so turn clockwise twice
+        // and put everything back if the second turn is blocked
+        let slots = self.slots;
+        let active_rustomino = self.active_rustomino.clone();
+        let last_kick = self.last_kick;
+        if self.rotate_active(Rotation::Cw) && self.rotate_active(Rotation::Cw) {
+            return true;
+        }
+        self.slots = slots;
+        self.active_rustomino = active_rustomino;
+        self.last_kick = last_kick;
+        self.update_ghost_rustomino();
+        false
+    }
+
     /// Attempt to translate the active rustomino.
     /// Return true if possible
     pub fn translate_active(&mut self, direction: TranslationDirection) -> bool {
@@ -148,17 +175,42 @@ impl Playfield {
             active_rustomino,
             direction.get_translation(),
         );
+        self.last_kick = None;
 
-        self.update_ghost_rustomino(true);
+        self.update_ghost_rustomino();
 
         true
     }
 
+    /// returns the height of the stack in each column
+    pub fn column_heights(&self) -> [usize; PLAYFIELD_SLOTS[0]] {
+        column_heights(&self.slots)
+    }
+
+    /// returns how many rows a hard drop would move the active rustomino
+    pub fn active_drop_distance(&self) -> usize {
+        let Some(active_rustomino) = self.active_rustomino.as_ref() else {
+            return 0;
+        };
+        get_hard_drop_translation(&self.slots, active_rustomino)[1].unsigned_abs() as usize
+    }
+
+    /// returns the slots the active rustomino would land in if it was hard dropped
+    /// without moving it, None when there's no active rustomino
+    pub fn active_landing_slots(&self) -> Option<[IVec2; 4]> {
+        let active_rustomino = self.active_rustomino.as_ref()?;
+        let delta = get_hard_drop_translation(&self.slots, active_rustomino);
+        Some(active_rustomino.translated(&delta))
+    }
+
     pub fn hard_drop_active(&mut self) {
         let Some(active_rustomino) = self.active_rustomino.as_mut() else {
             return;
         };
         let delta = get_hard_drop_translation(&self.slots, active_rustomino);
+        if delta != IVec2::ZERO {
+            self.last_kick = None;
+        }
         set_playfield_slot_states(
             &mut self.slots,
             &active_rustomino.playfield_slots(),
@@ -182,7 +234,7 @@ impl Playfield {
 
             // prepare for the next rustomino
             self.active_rustomino = None;
-            self.update_ghost_rustomino(true);
+            self.update_ghost_rustomino();
         }
     }
 
@@ -202,9 +254,147 @@ impl Playfield {
                 active_rustomino,
                 TranslationDirection::Down.get_translation(),
             );
+            self.last_kick = None;
+            // the cells the block left may belong to the ghost
+            self.update_ghost_rustomino();
+        }
+    }
+
+    /// checks to see if the active rustomino is in a t-spin position
+    /// using the guideline 3 corner rule
+    pub fn active_t_spin(&self) -> TSpin {
+        let Some(rustomino) = &self.active_rustomino else {
+            return TSpin::None;
+        };
+        // only a T rotated into place can t-spin
+        let Some(kick) = self.last_kick else {
+            return TSpin::None;
+        };
+        if rustomino.rtype != RustominoType::T {
+            return TSpin::None;
+        }
+
+        // the first block of a T is always its center
+        let center = rustomino.playfield_slots()[0];
+        // the corners on the side the T is pointing to
+        let (front, back) = match rustomino.rotation.direction() {
+            Direction::N => ([(-1, 1), (1, 1)], [(-1, -1), (1, -1)]),
+            Direction::E => ([(1, 1), (1, -1)], [(-1, 1), (-1, -1)]),
+            Direction::S => ([(-1, -1), (1, -1)], [(-1, 1), (1, 1)]),
+            Direction::W => ([(-1, 1), (-1, -1)], [(1, 1), (1, -1)]),
+        };
+        let occupied = |corners: [(i32, i32); 2]| {
+            corners
+                .iter()
+                .filter(|(x, y)| corner_occupied(&self.slots, center + IVec2::new(*x, *y)))
+                .count()
+        };
+        let front_occupied = occupied(front);
+        let back_occupied = occupied(back);
+
+        if front_occupied + back_occupied < 3 {
+            TSpin::None
+        } else if front_occupied == 2 || kick == T_SPIN_UPGRADE_KICK {
+            TSpin::Full
+        } else {
+            TSpin::Mini
         }
     }
 
+    /// returns true if the active rustomino was rotated into a spot
+    /// it can't move out of in any direction, used to award spins to any block
+    pub fn active_immobile_spin(&self) -> bool {
+        let Some(rustomino) = &self.active_rustomino else {
+            return false;
+        };
+        if self.last_kick.is_none() {
+            return false;
+        }
+        [
+            TranslationDirection::LEFT_TRANSLATION,
+            TranslationDirection::RIGHT_TRANSLATION,
+            TranslationDirection::DOWN_TRANSLATION,
+            IVec2::new(0, 1),
+        ]
+        .iter()
+        .all(|delta| check_collision(&self.slots, rustomino.translated(delta)))
+    }
+
+    /// returns every final position a new rustomino of the given type
+    /// can reach by rotating at spawn, moving sideways and hard dropping
+    /// works on copies of the slots so the playfield isn't changed
+    pub fn placements(&self, rtype: RustominoType) -> Vec<Placement> {
+        self.placements_from(Rustomino::new(rtype))
+    }
+
+    /// returns every final position the active rustomino can reach
+    /// from where it is now
+    pub fn active_placements(&self) -> Vec<Placement> {
+        match &self.active_rustomino {
+            Some(rustomino) => self.placements_from(rustomino.clone()),
+            None => Vec::new(),
+        }
+    }
+
+    fn placements_from(&self, mut rustomino: Rustomino) -> Vec<Placement> {
+        let rtype = rustomino.rtype;
+        // only locked blocks matter, ignore the active and ghost rustominos
+        let mut locked_slots = self.slots;
+        for slot in locked_slots.iter_mut().flatten() {
+            if !slot.is_locked() {
+                *slot = SlotState::Empty;
+            }
+        }
+
+        let mut placements: Vec<Placement> = Vec::new();
+        for rotation in 0..4 {
+            if rotation > 0 {
+                let Some((_, kick)) = check_rotation(&locked_slots, &rustomino, &Rotation::Cw)
+                else {
+                    break;
+                };
+                rustomino.rotate(&Rotation::Cw, &kick);
+            }
+            if check_collision(&locked_slots, rustomino.playfield_slots()) {
+                break;
+            }
+
+            // slide as far as possible in each direction
+            for direction in [TranslationDirection::Left, TranslationDirection::Right] {
+                let mut column = 0;
+                let mut moved = rustomino.clone();
+                loop {
+                    let drop = get_hard_drop_translation(&locked_slots, &moved);
+                    let blocks = moved.translated(&drop);
+                    let mut key = blocks.map(|b| (b[0], b[1]));
+                    key.sort();
+                    // different rotations can end up in the same place
+                    if !placements.iter().any(|p| {
+                        let mut other = p.blocks.map(|b| (b[0], b[1]));
+                        other.sort();
+                        other == key
+                    }) {
+                        placements.push(Placement::new(
+                            locked_slots,
+                            rtype,
+                            rotation,
+                            column,
+                            blocks,
+                        ));
+                    }
+
+                    let translation = direction.get_translation();
+                    if check_collision(&locked_slots, moved.translated(&translation)) {
+                        break;
+                    }
+                    moved.translate(translation);
+                    column += translation[0];
+                }
+            }
+        }
+        placements
+    }
+
     pub fn clear_completed_lines(&mut self) -> Vec<usize> {
         let completed_lines = self.get_complete_lines();
         let num_completed_lines = completed_lines.len();
@@ -215,48 +405,80 @@ impl Playfield {
         log::trace!("clearing lines before: playfield:\n{}", self);
         log::info!("clearing completed lines: {:?}", completed_lines);
 
-        // iterate through the slots
-        // clearing completed lines
-        self.slots
-            .iter_mut()
+        // keep the rows that weren't cleared in order from the bottom
+        // and pad the top of the playfield with empty rows
+        let mut slots = [[SlotState::Empty; PLAYFIELD_SLOTS[0]]; PLAYFIELD_SLOTS[1]];
+        let remaining = self
+            .slots
+            .iter()
             .enumerate()
-            .filter(|(y, _)| completed_lines.contains(y))
-            .for_each(|(_, slots_x)| {
-                for slot in slots_x.iter_mut() {
-                    *slot = SlotState::Empty;
+            .filter(|(y, _)| !completed_lines.contains(y))
+            .map(|(_, slots_x)| *slots_x);
+        for (row, slots_x) in slots.iter_mut().zip(remaining) {
+            *row = slots_x;
+        }
+        self.slots = slots;
+
+        log::trace!("clearing lines after: playfield:\n{}", self);
+        self.update_ghost_rustomino();
+        completed_lines
+    }
+
+    /// pushes the locked blocks up and fills the bottom rows with garbage
+    /// leaving an empty slot in the hole column of each row
+    /// returns false if locked blocks were pushed out the top (game over)
+    pub fn add_garbage(&mut self, rows: usize, hole: usize) -> bool {
+        log::info!("adding {} rows of garbage, hole at {}", rows, hole);
+        let mut ok = true;
+        let mut slots = [[SlotState::Empty; PLAYFIELD_SLOTS[0]]; PLAYFIELD_SLOTS[1]];
+        for (y, row) in self.slots.iter().enumerate() {
+            for (x, slot) in row.iter().enumerate().filter(|(_, slot)| slot.is_locked()) {
+                match slots.get_mut(y + rows) {
+                    Some(new_row) => new_row[x] = *slot,
+                    None => ok = false,
                 }
-            });
-
-        log::trace!("clearing lines middle: playfield:\n{}", self);
-
-        // then "move" the states of the slots above cleared lines down
-        // starts at the highest cleared line, and moves block states down
-        // this can probably be improved
-        for line in completed_lines.iter().rev() {
-            for y in *line..self.slots.len() {
-                for x in 0..self.slots[y].len() {
-                    // is this line is the very top row
-                    if y + 1 >= PLAYFIELD_SLOTS[1] {
-                        self.slots[y][x] = SlotState::Empty; // set all slots to empty
-                    } else {
-                        self.slots[y][x] = self.slots[y + 1][x]; // cope from the line above
-                    }
+            }
+        }
+        for row in slots.iter_mut().take(rows) {
+            for (x, slot) in row.iter_mut().enumerate() {
+                if x != hole {
+                    *slot = SlotState::Garbage;
                 }
             }
         }
+        self.slots = slots;
 
-        log::trace!("clearing lines after: playfield:\n{}", self);
-        self.update_ghost_rustomino(false);
-        completed_lines
+        // push the active rustomino up out of the garbage
+        if let Some(active_rustomino) = self.active_rustomino.as_mut() {
+            while check_collision(&self.slots, active_rustomino.playfield_slots()) {
+                if active_rustomino
+                    .playfield_slots()
+                    .iter()
+                    .any(|b| b[1] + 1 >= PLAYFIELD_SLOTS[1] as i32)
+                {
+                    ok = false;
+                    break;
+                }
+                active_rustomino.translate(IVec2::new(0, 1));
+            }
+            if ok {
+                set_playfield_slot_states(
+                    &mut self.slots,
+                    &active_rustomino.playfield_slots(),
+                    SlotState::Occupied(active_rustomino.rtype),
+                );
+            }
+        }
+        self.update_ghost_rustomino();
+        ok
     }
 
     /// Returns the get complete lines of this [`Playfield`].
-    fn get_complete_lines(&self) -> Vec<usize> {
+    pub fn get_complete_lines(&self) -> Vec<usize> {
         let mut complete_lines = vec![];
         'outer: for (i, line) in self.slots.iter().enumerate() {
             for slot in line {
-                // compare variant ignoring the value
-                if !variants_equal(slot, &SlotState::Locked(RustominoType::I)) {
+                if !slot.is_locked() {
                     continue 'outer;
                 }
             }
@@ -265,59 +487,83 @@ impl Playfield {
         complete_lines
     }
 
-    fn update_ghost_rustomino(&mut self, translating: bool) {
+    fn update_ghost_rustomino(&mut self) {
+        // clear the previous ghost, wherever it was
+        for slot in self.slots.iter_mut().flatten() {
+            if matches!(slot, SlotState::Ghost(_)) {
+                *slot = SlotState::Empty;
+            }
+        }
+
         let Some(active_rustomino) = &self.active_rustomino else {
             log::debug!("active_rustomino is None, removing ghost rustomino");
-            if !translating {
-                if let Some(ghost_rustomino) = self.ghost_rustomino.as_mut() {
-                    set_playfield_slot_states(
-                        &mut self.slots,
-                        &ghost_rustomino.playfield_slots(),
-                        SlotState::Empty,
-                    );
-                }
-            }
             self.ghost_rustomino = None;
             return;
         };
 
         log::debug!("updating ghost location");
         let drop_translation = get_hard_drop_translation(&self.slots, active_rustomino);
-        if let Some(ghost_rustomino) = self.ghost_rustomino.as_mut() {
-            if translating {
-                for slot in ghost_rustomino.playfield_slots() {
-                    if !variants_equal(
-                        &self.slots[slot[1] as usize][slot[0] as usize],
-                        &SlotState::Occupied(RustominoType::I),
-                    ) {
-                        self.slots[slot[1] as usize][slot[0] as usize] = SlotState::Empty;
-                    }
-                }
-            }
+        let mut ghost_rustomino = active_rustomino.clone();
+        ghost_rustomino.translate(drop_translation);
 
-            ghost_rustomino.blocks = active_rustomino.blocks;
-            ghost_rustomino.translation = active_rustomino.translation;
-
-            // perform the translation
-            ghost_rustomino.translate(drop_translation);
+        log::trace!(
+            "update_ghost_rustomino: new ghost rustomino location: {:?}",
+            ghost_rustomino.playfield_slots()
+        );
 
-            log::trace!(
-                "update_ghost_rustomino: new ghost rustomino location: {:?}",
-                ghost_rustomino.playfield_slots()
-            );
+        // the ghost is never written over the active rustomino
+        // so a resting block doesn't lose any of its cells
+        for slot in ghost_rustomino.playfield_slots() {
+            let slot_state = &mut self.slots[slot[1] as usize][slot[0] as usize];
+            if *slot_state == SlotState::Empty {
+                *slot_state = SlotState::Ghost(ghost_rustomino.rtype);
+            }
+        }
+        self.ghost_rustomino = Some(ghost_rustomino);
+    }
+}
 
-            // set the new slot states to occupied
-            for slot in ghost_rustomino.playfield_slots() {
-                if !variants_equal(
-                    &self.slots[slot[1] as usize][slot[0] as usize],
-                    &SlotState::Occupied(RustominoType::I),
-                ) {
-                    self.slots[slot[1] as usize][slot[0] as usize] =
-                        SlotState::Ghost(ghost_rustomino.rtype);
+/// logs an error for any rustomino that would spawn outside the playfield's walls
+/// or outside the buffer rows, returns false if any spawn is out of bounds
+/// the flat side up spawns reach into the top visible row so they're only
+/// checked against the walls and the top of the playfield
+pub fn validate_spawn_positions() -> bool {
+    let mut valid = true;
+    for rtype in RustominoType::iter() {
+        let spawns = [
+            ("standard", Rustomino::new(rtype), PLAYFIELD_SIZE[1]),
+            ("flat side up", Rustomino::new(rtype).flat_side_up(), 0),
+        ];
+        for (spawn, rustomino, lowest_row) in spawns {
+            for slot in rustomino.playfield_slots() {
+                let in_bounds = (0..PLAYFIELD_SLOTS[0] as i32).contains(&slot.x)
+                    && (lowest_row..PLAYFIELD_SLOTS[1] as i32).contains(&slot.y);
+                if !in_bounds {
+                    log::error!(
+                        "{:?} {} spawn has a block out of bounds at {:?}",
+                        rtype,
+                        spawn,
+                        slot
+                    );
+                    valid = false;
                 }
             }
         }
     }
+    valid
+}
+
+/// returns the height of the highest locked slot in each column
+/// the active rustomino and its ghost aren't part of the stack
+pub fn column_heights(playfield_slots: &PlayfieldSlots) -> [usize; PLAYFIELD_SLOTS[0]] {
+    let mut heights = [0; PLAYFIELD_SLOTS[0]];
+    for (x, height) in heights.iter_mut().enumerate() {
+        *height = (0..PLAYFIELD_SLOTS[1])
+            .rev()
+            .find(|y| playfield_slots[*y][x].is_locked())
+            .map_or(0, |y| y + 1);
+    }
+    heights
 }
 
 fn get_hard_drop_translation(playfield_slots: &PlayfieldSlots, rustomino: &Rustomino) -> IVec2 {
@@ -362,11 +608,7 @@ fn check_collision(playfield_slots: &PlayfieldSlots, block_locations: [IVec2; 4]
             log::trace!("collided with bottom wall: {:?}", block_locations);
             return true;
         }
-        // slots[y][x] compare variant ignoring value
-        if variants_equal(
-            &playfield_slots[location[1] as usize][location[0] as usize],
-            &SlotState::Locked(RustominoType::I),
-        ) {
+        if playfield_slots[location[1] as usize][location[0] as usize].is_locked() {
             log::trace!("collided with locked block: {:?}", block_locations);
             return true;
         }
@@ -374,17 +616,31 @@ fn check_collision(playfield_slots: &PlayfieldSlots, block_locations: [IVec2; 4]
     false
 }
 
+/// returns the index and translation of the first wall kick test
+/// which allows the rustomino to rotate
 fn check_rotation(
     playfield_slots: &PlayfieldSlots,
     rustomino: &Rustomino,
     rotation: &Rotation,
-) -> Option<IVec2> {
+) -> Option<(usize, IVec2)> {
     let wall_kick_tests = rustomino.wall_kick_tests(rotation);
     let rotated_blocks = rustomino.rotated(rotation);
     wall_kick_tests
         .iter()
-        .find(|x| !check_collision(playfield_slots, translated(&rotated_blocks, x)))
-        .copied()
+        .enumerate()
+        .find(|(_, x)| !check_collision(playfield_slots, translated(&rotated_blocks, x)))
+        .map(|(i, x)| (i, *x))
+}
+
+/// walls and the floor count as occupied corners for t-spins
+fn corner_occupied(playfield_slots: &PlayfieldSlots, location: IVec2) -> bool {
+    if location[0] < 0 || location[0] >= PLAYFIELD_SLOTS[0] as i32 || location[1] < 0 {
+        return true;
+    }
+    if location[1] >= PLAYFIELD_SLOTS[1] as i32 {
+        return false;
+    }
+    playfield_slots[location[1] as usize][location[0] as usize].is_locked()
 }
 
 fn translate_rustomino(
@@ -437,6 +693,52 @@ impl Display for Playfield {
     }
 }
 
+/// a final position a rustomino can be dropped into
+#[derive(Debug, Clone)]
+pub struct Placement {
+    pub rotation: usize, // clockwise rotations from the spawn orientation
+    pub column: i32,     // columns moved from the spawn position, negative is left
+    pub blocks: [IVec2; 4],
+    pub slots: PlayfieldSlots, // the playfield after locking and clearing lines
+    pub lines_cleared: usize,
+}
+
+impl Placement {
+    fn new(
+        locked_slots: PlayfieldSlots,
+        rtype: RustominoType,
+        rotation: usize,
+        column: i32,
+        blocks: [IVec2; 4],
+    ) -> Self {
+        let mut playfield = Playfield {
+            slots: locked_slots,
+            active_rustomino: None,
+            ghost_rustomino: None,
+            last_kick: None,
+        };
+        set_playfield_slot_states(&mut playfield.slots, &blocks, SlotState::Locked(rtype));
+        let lines_cleared = playfield.clear_completed_lines().len();
+        Placement {
+            rotation,
+            column,
+            blocks,
+            slots: playfield.slots,
+            lines_cleared,
+        }
+    }
+
+    /// the playfield left behind with no active rustomino, for looking further ahead
+    pub fn playfield(&self) -> Playfield {
+        Playfield {
+            slots: self.slots,
+            active_rustomino: None,
+            ghost_rustomino: None,
+            last_kick: None,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum TranslationDirection {
     Left,
@@ -457,12 +759,27 @@ impl TranslationDirection {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TSpin {
+    None,
+    Mini,
+    Full,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SlotState {
     Empty,
     Occupied(RustominoType),
     Locked(RustominoType),
     Ghost(RustominoType),
+    Garbage,
+}
+
+impl SlotState {
+    /// locked blocks and garbage can't be moved through
+    pub fn is_locked(&self) -> bool {
+        matches!(self, SlotState::Locked(_) | SlotState::Garbage)
+    }
 }
 
 impl Display for SlotState {
@@ -472,7 +789,246 @@ impl Display for SlotState {
             SlotState::Occupied(_) => write!(f, " #")?,
             SlotState::Locked(_) => write!(f, " @")?,
             SlotState::Ghost(_) => write!(f, " %")?,
+            SlotState::Garbage => write!(f, " *")?,
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // builds a playfield from rows drawn top to bottom, the last row is the floor
+    // any character other than a space or a dot is a locked slot
+    fn playfield(rows: &[&str]) -> Playfield {
+        let mut playfield = Playfield::new();
+        for (y, row) in rows.iter().rev().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                if c != ' ' && c != '.' {
+                    playfield.slots[y][x] = SlotState::Garbage;
+                }
+            }
+        }
+        playfield
+    }
+
+    // makes a rustomino facing direction with its first block at center the active one
+    fn set_active_at(
+        playfield: &mut Playfield,
+        rtype: RustominoType,
+        direction: Direction,
+        center: IVec2,
+    ) {
+        let mut rustomino = Rustomino::new(rtype).facing(direction);
+        rustomino.translate(center - rustomino.playfield_slots()[0]);
+        assert!(playfield.set_active(rustomino), "{}", playfield);
+    }
+
+    #[test]
+    fn mini_t_spin_single() {
+        let mut playfield = playfield(&[".@########"]);
+        set_active_at(
+            &mut playfield,
+            RustominoType::T,
+            Direction::N,
+            IVec2::new(1, 1),
+        );
+
+        // kicked off the block under the center into the wall
+        assert!(playfield.rotate_active(Rotation::Cw));
+        assert_eq!(playfield.last_kick, Some(1));
+        assert_eq!(playfield.active_t_spin(), TSpin::Mini);
+
+        playfield.lock_active();
+        assert_eq!(playfield.clear_completed_lines(), vec![0]);
+    }
+
+    #[test]
+    fn t_spin_triple() {
+        let mut playfield = playfield(&[
+            ".@........",
+            "..........",
+            "@.@@@@@@@@",
+            "@..@@@@@@@",
+            "@.@@@@@@@@",
+        ]);
+        set_active_at(
+            &mut playfield,
+            RustominoType::T,
+            Direction::N,
+            IVec2::new(2, 3),
+        );
+
+        assert!(playfield.rotate_active(Rotation::Cw));
+        assert_eq!(playfield.last_kick, Some(T_SPIN_UPGRADE_KICK));
+        assert_eq!(playfield.active_t_spin(), TSpin::Full);
+
+        playfield.lock_active();
+        assert_eq!(playfield.clear_completed_lines(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn t_spin_upgraded_by_the_last_kick() {
+        // one front corner is open which would only be a mini t-spin
+        let mut playfield = playfield(&[
+            ".@........",
+            "..........",
+            "@.@@@@@@@@",
+            "@..@@@@@@@",
+            "@.........",
+        ]);
+        set_active_at(
+            &mut playfield,
+            RustominoType::T,
+            Direction::N,
+            IVec2::new(2, 3),
+        );
+
+        assert!(playfield.rotate_active(Rotation::Cw));
+        assert_eq!(playfield.last_kick, Some(T_SPIN_UPGRADE_KICK));
+        assert_eq!(playfield.active_t_spin(), TSpin::Full);
+
+        // the same spot reached without the upgrade kick
+        playfield.last_kick = Some(0);
+        assert_eq!(playfield.active_t_spin(), TSpin::Mini);
+    }
+
+    #[test]
+    fn i_placements_on_an_empty_board() {
+        // 7 columns lying flat and 10 columns standing up
+        let placements = Playfield::new().placements(RustominoType::I);
+        assert_eq!(placements.len(), 17);
+        assert!(placements.iter().all(|p| p.lines_cleared == 0));
+        assert_eq!(placements.iter().filter(|p| p.rotation % 2 == 0).count(), 7);
+    }
+
+    #[test]
+    fn ghost_is_never_drawn_over_the_active_block() {
+        // an O one row above its landing spot, its ghost overlaps its bottom row
+        let mut playfield = Playfield::new();
+        let mut rustomino = Rustomino::new(RustominoType::O);
+        let lowest = rustomino
+            .playfield_slots()
+            .iter()
+            .map(|b| b.y)
+            .min()
+            .unwrap();
+        rustomino.translate(IVec2::new(0, 1 - lowest));
+        let active = rustomino.playfield_slots();
+        assert!(playfield.set_active(rustomino));
+
+        for slot in active {
+            assert_eq!(
+                playfield.slots[slot.y as usize][slot.x as usize],
+                SlotState::Occupied(RustominoType::O)
+            );
+        }
+        let ghosts: Vec<IVec2> = (0..PLAYFIELD_SLOTS[1])
+            .flat_map(|y| (0..PLAYFIELD_SLOTS[0]).map(move |x| (x, y)))
+            .filter(|(x, y)| matches!(playfield.slots[*y][*x], SlotState::Ghost(_)))
+            .map(|(x, y)| IVec2::new(x as i32, y as i32))
+            .collect();
+        // only the ghost's bottom row shows, on the floor right under the block
+        let mut expected: Vec<IVec2> = active
+            .iter()
+            .filter(|b| b.y == 1)
+            .map(|b| IVec2::new(b.x, 0))
+            .collect();
+        expected.sort_by_key(|b| b.x);
+        assert_eq!(expected.len(), 2);
+        assert_eq!(ghosts, expected);
+    }
+
+    #[test]
+    fn clears_lines_that_are_not_next_to_each_other() {
+        let mut cleared = playfield(&["@.........", "@@@@@@@@@@", ".@@@@@@@@@", "@@@@@@@@@@"]);
+        assert_eq!(cleared.clear_completed_lines(), vec![0, 2]);
+
+        // the rows that were left drop down in order
+        let expected = playfield(&["@.........", ".@@@@@@@@@"]);
+        assert_eq!(cleared.slots, expected.slots);
+    }
+
+    // rotates a rustomino pushed against the left wall (facing E)
+    // or the floor (facing N) and returns the wall kick it needed
+    fn kick_against(rtype: RustominoType, facing: Direction, rotation: Rotation) -> IVec2 {
+        let mut rustomino = Rustomino::new(rtype).facing(facing);
+        let slots = rustomino.playfield_slots();
+        let delta = match facing {
+            Direction::E => IVec2::new(-slots.iter().map(|b| b.x).min().unwrap(), 10 - slots[0].y),
+            _ => IVec2::new(0, -slots.iter().map(|b| b.y).min().unwrap()),
+        };
+        rustomino.translate(delta);
+        let unkicked = rustomino.rotated(&rotation);
+
+        let mut playfield = Playfield::new();
+        assert!(playfield.set_active(rustomino));
+        assert!(playfield.rotate_active(rotation));
+        playfield.active_rustomino.unwrap().playfield_slots()[0] - unkicked[0]
+    }
+
+    #[test]
+    fn wall_kicks_off_the_wall_and_floor() {
+        use RustominoType::*;
+        // (wall cw, wall ccw, floor cw, floor ccw) from the SRS kick tables
+        let kicks = [
+            (I, [(2, 0), (2, 0), (1, 2), (-1, 2)]),
+            (O, [(0, 0), (0, 0), (0, 0), (0, 0)]),
+            (T, [(1, 0), (1, 0), (-1, 1), (1, 1)]),
+            (L, [(1, 0), (1, 0), (-1, 1), (1, 1)]),
+            (J, [(1, 0), (1, 0), (-1, 1), (1, 1)]),
+            (S, [(1, 0), (1, 0), (-1, 1), (1, 1)]),
+            (Z, [(1, 0), (1, 0), (-1, 1), (1, 1)]),
+        ];
+        assert_eq!(kicks.len(), RustominoType::iter().count());
+        for (rtype, expected) in kicks {
+            let kicked = [
+                kick_against(rtype, Direction::E, Rotation::Cw),
+                kick_against(rtype, Direction::E, Rotation::Ccw),
+                kick_against(rtype, Direction::N, Rotation::Cw),
+                kick_against(rtype, Direction::N, Rotation::Ccw),
+            ];
+            assert_eq!(
+                kicked,
+                expected.map(|(x, y)| IVec2::new(x, y)),
+                "{:?}",
+                rtype
+            );
+        }
+    }
+
+    #[test]
+    fn column_heights_count_only_locked_slots() {
+        let mut playfield = playfield(&["...@......", "...@...@..", "@..@..@@..", "@@.@.@@@.@"]);
+        // the active block and its ghost sit above and in the gaps of the stack
+        playfield.slots[3][0] = SlotState::Occupied(RustominoType::I);
+        playfield.slots[0][2] = SlotState::Ghost(RustominoType::I);
+        playfield.slots[1][8] = SlotState::Ghost(RustominoType::I);
+        assert_eq!(playfield.column_heights(), [2, 1, 0, 4, 0, 1, 2, 3, 0, 1]);
+    }
+
+    #[test]
+    fn landing_slots_match_a_hard_drop() {
+        let mut playfield = playfield(&["@@@..@@@@@", "@@@@.@@@@@"]);
+        assert_eq!(playfield.active_landing_slots(), None);
+
+        set_active_at(
+            &mut playfield,
+            RustominoType::T,
+            Direction::S,
+            IVec2::new(4, 10),
+        );
+        let landing = playfield.active_landing_slots();
+        assert!(landing.is_some());
+
+        playfield.hard_drop_active();
+        assert_eq!(
+            landing,
+            playfield
+                .active_rustomino
+                .as_ref()
+                .map(|r| r.playfield_slots())
+        );
+    }
+}
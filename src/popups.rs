@@ -0,0 +1,41 @@
+// how long a popup stays on screen in seconds
+const POPUP_DURATION: f64 = 1.5;
+// fraction of the popup's duration spent fading out
+const POPUP_FADE_FRACTION: f64 = 0.5;
+// how far a popup floats upwards over its lifetime in pixels
+const POPUP_FLOAT_DISTANCE: f32 = 40.0;
+
+/// short lived text which floats over the playfield
+#[derive(Debug, Clone)]
+pub struct Popup {
+    pub text: String,
+    time: f64,
+}
+
+impl Popup {
+    pub fn new(text: String) -> Self {
+        Popup { text, time: 0.0 }
+    }
+
+    pub fn update(&mut self, delta_time: f64) {
+        self.time += delta_time;
+    }
+
+    pub fn expired(&self) -> bool {
+        self.time >= POPUP_DURATION
+    }
+
+    /// opacity of the popup, fades out towards the end of its lifetime
+    pub fn alpha(&self) -> f32 {
+        let fade_start = POPUP_DURATION * (1.0 - POPUP_FADE_FRACTION);
+        if self.time <= fade_start {
+            return 1.0;
+        }
+        (1.0 - (self.time - fade_start) / (POPUP_DURATION - fade_start)).clamp(0.0, 1.0) as f32
+    }
+
+    /// vertical offset of the popup, it floats upwards over its lifetime
+    pub fn offset(&self) -> f32 {
+        -((self.time / POPUP_DURATION) as f32 * POPUP_FLOAT_DISTANCE)
+    }
+}
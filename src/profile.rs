@@ -0,0 +1,78 @@
+use std::path::{Path, PathBuf};
+
+const PROFILES_DIR: &str = "profiles";
+const PROFILE_FILE: &str = "profile.txt";
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// the folder a profile keeps its settings, scores, records and saved game in
+///
+/// the default profile uses the user's data directory itself so the files
+/// from before profiles carry on being used, other profiles are the folders
+/// in the profiles folder, named after the player
+pub fn dir(data_dir: &Path, name: &str) -> PathBuf {
+    if name == DEFAULT_PROFILE {
+        data_dir.to_path_buf()
+    } else {
+        data_dir.join(PROFILES_DIR).join(name)
+    }
+}
+
+/// the default profile followed by the other profiles sorted by name
+pub fn list(data_dir: &Path) -> Vec<String> {
+    let path = data_dir.join(PROFILES_DIR);
+    let mut names = match std::fs::read_dir(&path) {
+        Ok(entries) => entries
+            .flatten()
+            .filter(|entry| entry.path().is_dir())
+            .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+            .filter(|name| name != DEFAULT_PROFILE)
+            .collect::<Vec<_>>(),
+        Err(e) => {
+            log::info!("unable to read profiles from {:?}: {}", path, e);
+            Vec::new()
+        }
+    };
+    names.sort();
+    names.insert(0, DEFAULT_PROFILE.to_string());
+    names
+}
+
+/// the profile that was in use last time
+/// falls back to the default profile if it's missing or has been removed
+pub fn load_current(data_dir: &Path) -> String {
+    let path = data_dir.join(PROFILE_FILE);
+    let name = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents.trim().to_string(),
+        Err(e) => {
+            log::info!("unable to read profile from {:?}: {}", path, e);
+            return DEFAULT_PROFILE.to_string();
+        }
+    };
+    if list(data_dir).contains(&name) {
+        name
+    } else {
+        log::warn!("profile {} no longer exists, using the default", name);
+        DEFAULT_PROFILE.to_string()
+    }
+}
+
+/// remembers the profile in use for the next time the game starts
+pub fn save_current(data_dir: &Path, name: &str) {
+    let path = data_dir.join(PROFILE_FILE);
+    match std::fs::create_dir_all(data_dir).and_then(|_| std::fs::write(&path, name)) {
+        Ok(_) => log::info!("saved profile to {:?}", path),
+        Err(e) => log::warn!("unable to save profile to {:?}: {}", path, e),
+    }
+}
+
+/// the profile after (or before) the provided one, wrapping around
+pub fn cycle(data_dir: &Path, current: &str, forward: bool) -> String {
+    let names = list(data_dir);
+    let i = names.iter().position(|n| n == current).unwrap_or(0);
+    let i = if forward {
+        (i + 1) % names.len()
+    } else {
+        (i + names.len() - 1) % names.len()
+    };
+    names[i].clone()
+}
@@ -0,0 +1,76 @@
+use std::{fmt::Display, path::Path};
+
+const RECORDS_FILE: &str = "records.txt";
+
+/// the best clears from marathon games, kept for the session and all time
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Records {
+    pub longest_combo: usize,     // most blocks in a row that each cleared lines
+    pub best_back_to_back: usize, // most tetrises and spins in a row without an easier clear
+    pub most_lines: usize,        // most lines cleared by a single block
+}
+
+impl Records {
+    /// loads the all time records from the user's data directory
+    /// missing or invalid records start again from zero
+    pub fn load(dir: &Path) -> Records {
+        let path = dir.join(RECORDS_FILE);
+        let mut records = Records::default();
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                log::info!("unable to read records from {:?}: {}", path, e);
+                return records;
+            }
+        };
+        for line in contents.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            if !records.set(key, value) {
+                log::warn!("ignoring invalid record: {}", line);
+            }
+        }
+        records
+    }
+
+    /// saves the records to the user's data directory
+    pub fn save(&self, dir: &Path) {
+        let path = dir.join(RECORDS_FILE);
+        match std::fs::create_dir_all(dir).and_then(|_| std::fs::write(&path, self.to_string())) {
+            Ok(_) => log::info!("saved records to {:?}", path),
+            Err(e) => log::warn!("unable to save records to {:?}: {}", path, e),
+        }
+    }
+
+    /// keeps the better of each record, returns true if any were beaten
+    pub fn improve(&mut self, other: &Records) -> bool {
+        let previous = *self;
+        self.longest_combo = self.longest_combo.max(other.longest_combo);
+        self.best_back_to_back = self.best_back_to_back.max(other.best_back_to_back);
+        self.most_lines = self.most_lines.max(other.most_lines);
+        *self != previous
+    }
+
+    // sets a record from its saved key and value, returns false if either is invalid
+    fn set(&mut self, key: &str, value: &str) -> bool {
+        let Ok(value) = value.parse() else {
+            return false;
+        };
+        match key {
+            "longest_combo" => self.longest_combo = value,
+            "best_back_to_back" => self.best_back_to_back = value,
+            "most_lines" => self.most_lines = value,
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl Display for Records {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "longest_combo={}", self.longest_combo)?;
+        writeln!(f, "best_back_to_back={}", self.best_back_to_back)?;
+        writeln!(f, "most_lines={}", self.most_lines)
+    }
+}
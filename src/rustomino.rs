@@ -2,8 +2,12 @@ use ::rand::{seq::SliceRandom, SeedableRng};
 use ggez::{glam::IVec2, graphics::Color};
 use strum::{EnumIter, IntoEnumIterator};
 
-const I_START_TRANSLATION: IVec2 = IVec2::new(3, 18);
-const O_T_L_J_S_Z_START_TRANSLATION: IVec2 = IVec2::new(3, 19);
+use crate::playfield::PLAYFIELD_SLOTS;
+
+// the I and O blocks sit in a 4 wide box, the others in a 3 wide box
+const I_START_TRANSLATION: IVec2 = IVec2::new(spawn_column(PLAYFIELD_SLOTS[0], 4), 18);
+const O_START_TRANSLATION: IVec2 = IVec2::new(spawn_column(PLAYFIELD_SLOTS[0], 4), 19);
+const T_L_J_S_Z_START_TRANSLATION: IVec2 = IVec2::new(spawn_column(PLAYFIELD_SLOTS[0], 3), 19);
 
 const I_BLOCKS: [IVec2; 4] = [
     IVec2::new(0, 2),
@@ -299,7 +303,7 @@ const JLSTZ_WALL_KICK_TESTS: [[IVec2; 5]; 8] = [
         IVec2::new(-1, -2),
     ],
     [
-        // E->W ( 0, 0),(1, 0),(1,1),( 0,-2),(1,-2)
+        // S->W ( 0, 0),(1, 0),(1,1),( 0,-2),(1,-2)
         IVec2::new(0, 0),
         IVec2::new(1, 0),
         IVec2::new(1, 1),
@@ -307,7 +311,7 @@ const JLSTZ_WALL_KICK_TESTS: [[IVec2; 5]; 8] = [
         IVec2::new(1, -2),
     ],
     [
-        // W->E ( 0, 0),(-1, 0),(-1,-1),( 0,2),(-1,2)
+        // W->S ( 0, 0),(-1, 0),(-1,-1),( 0,2),(-1,2)
         IVec2::new(0, 0),
         IVec2::new(-1, 0),
         IVec2::new(-1, -1),
@@ -366,7 +370,7 @@ const I_WALL_KICK_TESTS: [[IVec2; 5]; 8] = [
         IVec2::new(-2, 1),
     ],
     [
-        // E->W ( 0, 0),(2, 0),(-1, 0),(2,1),(-1,-2)
+        // S->W ( 0, 0),(2, 0),(-1, 0),(2,1),(-1,-2)
         IVec2::new(0, 0),
         IVec2::new(2, 0),
         IVec2::new(-1, 0),
@@ -374,7 +378,7 @@ const I_WALL_KICK_TESTS: [[IVec2; 5]; 8] = [
         IVec2::new(-1, -2),
     ],
     [
-        // W->E ( 0, 0),(-2, 0),(1, 0),(-2,-1),(1,2)
+        // W->S ( 0, 0),(-2, 0),(1, 0),(-2,-1),(1,2)
         IVec2::new(0, 0),
         IVec2::new(-2, 0),
         IVec2::new(1, 0),
@@ -420,32 +424,32 @@ impl Rustomino {
                 RustominoType::O => (
                     RustominoRotation::new(O_ROTATIONS),
                     O_BLOCKS,
-                    O_T_L_J_S_Z_START_TRANSLATION,
+                    O_START_TRANSLATION,
                 ),
                 RustominoType::T => (
                     RustominoRotation::new(T_ROTATIONS),
                     T_BLOCKS,
-                    O_T_L_J_S_Z_START_TRANSLATION,
+                    T_L_J_S_Z_START_TRANSLATION,
                 ),
                 RustominoType::L => (
                     RustominoRotation::new(L_ROTATIONS),
                     L_BLOCKS,
-                    O_T_L_J_S_Z_START_TRANSLATION,
+                    T_L_J_S_Z_START_TRANSLATION,
                 ),
                 RustominoType::J => (
                     RustominoRotation::new(J_ROTATIONS),
                     J_BLOCKS,
-                    O_T_L_J_S_Z_START_TRANSLATION,
+                    T_L_J_S_Z_START_TRANSLATION,
                 ),
                 RustominoType::S => (
                     RustominoRotation::new(S_ROTATIONS),
                     S_BLOCKS,
-                    O_T_L_J_S_Z_START_TRANSLATION,
+                    T_L_J_S_Z_START_TRANSLATION,
                 ),
                 RustominoType::Z => (
                     RustominoRotation::new(Z_ROTATIONS),
                     Z_BLOCKS,
-                    O_T_L_J_S_Z_START_TRANSLATION,
+                    T_L_J_S_Z_START_TRANSLATION,
                 ),
             }
         };
@@ -462,6 +466,27 @@ impl Rustomino {
         Rustomino::new(self.rtype)
     }
 
+    /// turns T, L and J rustominos so they spawn flat side up,
+    /// the other rustominos look the same either way
+    pub fn flat_side_up(mut self) -> Rustomino {
+        if matches!(
+            self.rtype,
+            RustominoType::T | RustominoType::L | RustominoType::J
+        ) {
+            self.rotate(&Rotation::Cw, &IVec2::ZERO);
+            self.rotate(&Rotation::Cw, &IVec2::ZERO);
+        }
+        self
+    }
+
+    /// turns the rustomino clockwise until it faces the provided direction
+    pub fn facing(mut self, direction: Direction) -> Rustomino {
+        while self.rotation.direction() != direction {
+            self.rotate(&Rotation::Cw, &IVec2::ZERO);
+        }
+        self
+    }
+
     pub fn translate(&mut self, delta: IVec2) {
         self.translation += delta;
     }
@@ -505,6 +530,13 @@ impl Rustomino {
     }
 }
 
+/// the leftmost column of a block's spawn box so it's centered on the board
+/// when it can't be exactly centered it leans left, like the guideline's
+/// 3 wide blocks spawning in columns 3 to 5 of a 10 wide board
+const fn spawn_column(board_width: usize, box_width: usize) -> i32 {
+    (board_width.saturating_sub(box_width) / 2) as i32
+}
+
 pub fn translated(blocks: &[IVec2; 4], delta: &IVec2) -> [IVec2; 4] {
     [
         blocks[0] + *delta,
@@ -553,7 +585,7 @@ pub enum RustominoState {
     Lockdown { time: f64 },
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter)]
 pub enum Direction {
     N,
     E,
@@ -679,6 +711,10 @@ impl RustominoRotation {
     fn rotate(&mut self, rotation: &Rotation) {
         self.direction = self.direction.rotate(rotation)
     }
+
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
 }
 
 #[inline(always)]
@@ -694,34 +730,175 @@ fn neg_trans(block_trans: [IVec2; 4]) -> [IVec2; 4] {
 pub struct RustominoBag {
     bag: Vec<RustominoType>, // contains the next rustomino types, shuffled
     rng: rand_xoshiro::Xoshiro256PlusPlus,
+    seed: u64,
+    script: Vec<RustominoType>, // a sequence repeated in place of the shuffled bag
+    droughts: [u32; 7],         // draws since each rustomino type was last drawn
+    drawn: u64,                 // rustominos drawn from the bag so far
 }
 
 impl RustominoBag {
     pub fn new() -> Self {
+        Self::with_seed(::rand::random())
+    }
+
+    /// create a bag whose sequence of rustomino types
+    /// is fully determined by the provided seed
+    pub fn with_seed(seed: u64) -> Self {
+        log::info!("seeding rustomino bag: {}", seed);
         RustominoBag {
             bag: Vec::new(),
-            rng: rand_xoshiro::Xoshiro256PlusPlus::from_entropy(),
+            rng: rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(seed),
+            seed,
+            script: Vec::new(),
+            droughts: [0; 7],
+            drawn: 0,
+        }
+    }
+
+    /// recreate a seeded bag that has already had drawn rustominos taken out
+    pub fn resumed(seed: u64, drawn: u64) -> Self {
+        let mut bag = Self::with_seed(seed);
+        for _ in 0..drawn {
+            bag.get_next_type();
         }
+        bag
+    }
+
+    /// create a bag that repeats the provided sequence of rustomino types
+    /// used to drill specific setups
+    pub fn scripted(script: Vec<RustominoType>) -> Self {
+        log::info!("scripting rustomino bag: {:?}", script);
+        RustominoBag {
+            script,
+            ..Self::with_seed(0)
+        }
+    }
+
+    /// a shuffled bag that deals the provided rustomino types first
+    pub fn queued(queue: Vec<RustominoType>) -> Self {
+        log::info!("queueing rustominos: {:?}", queue);
+        let mut bag = Self::new();
+        // the bag is drawn from the back so the queue goes in reversed
+        bag.bag = queue.into_iter().rev().collect();
+        bag
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn drawn(&self) -> u64 {
+        self.drawn
+    }
+
+    /// the number of rustominos drawn since the provided type last came out of the bag
+    pub fn drought(&self, rtype: RustominoType) -> u32 {
+        self.droughts[rtype as usize]
     }
 
     pub fn get_next(&mut self) -> Rustomino {
-        // make sure the bag isn't empty
-        self.fill_bag();
+        Rustomino::new(self.get_next_type())
+    }
 
-        let rtype = self.bag.pop().expect("rustomino bag is empty");
+    /// returns the next rustomino type in the sequence
+    pub fn get_next_type(&mut self) -> RustominoType {
+        // the bag is refilled whenever it runs out so this always finds a type
+        let rtype = loop {
+            match self.bag.pop() {
+                Some(rtype) => break rtype,
+                None => self.fill_bag(),
+            }
+        };
         log::info!("next rustomino type: {:?}", rtype);
 
-        Rustomino::new(rtype)
+        for drought in self.droughts.iter_mut() {
+            *drought += 1;
+        }
+        self.droughts[rtype as usize] = 0;
+        self.drawn += 1;
+
+        rtype
+    }
+
+    /// returns the next count rustomino types in the order they'll be drawn
+    /// without taking them out of the bag
+    pub fn peek(&mut self, count: usize) -> Vec<RustominoType> {
+        while self.bag.len() < count {
+            self.fill_bag();
+        }
+        self.bag.iter().rev().take(count).copied().collect()
     }
 
     // add one of each rustomino type to bag
     // then shuffle the bag
+    // new types go in ahead of the ones already waiting
     fn fill_bag(&mut self) {
-        if !self.bag.is_empty() {
+        // the bag is drawn from the back so the script goes in reversed
+        if !self.script.is_empty() {
+            self.bag.splice(0..0, self.script.iter().rev().copied());
+            log::debug!("filled rustomino bag from script: {:?}", self.bag);
             return;
         }
-        self.bag.append(&mut RustominoType::iter().collect());
-        self.bag.shuffle(&mut self.rng);
+        let mut types: Vec<RustominoType> = RustominoType::iter().collect();
+        types.shuffle(&mut self.rng);
+        self.bag.splice(0..0, types);
         log::debug!("filled rustomino bag: {:?}", self.bag);
     }
 }
+
+impl Iterator for RustominoBag {
+    type Item = RustominoType;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.get_next_type())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // any fixed seed, so a failure can be reproduced
+    const TEST_SEED: u64 = 42;
+
+    #[test]
+    fn each_bag_holds_every_type_once() {
+        let types: Vec<RustominoType> = RustominoBag::with_seed(TEST_SEED).take(7 * 500).collect();
+        for window in types.chunks(7) {
+            for rtype in RustominoType::iter() {
+                assert_eq!(
+                    window.iter().filter(|t| **t == rtype).count(),
+                    1,
+                    "{:?} in bag {:?}",
+                    rtype,
+                    window
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn peek_matches_the_draws_that_follow() {
+        let mut bag = RustominoBag::with_seed(TEST_SEED);
+        for _ in 0..1000 {
+            let peeked = bag.peek(20);
+            let drawn: Vec<RustominoType> = (0..20).map(|_| bag.get_next_type()).collect();
+            assert_eq!(peeked, drawn);
+            // an uneven number of draws leaves the bags part used
+            bag.get_next_type();
+        }
+        assert_eq!(bag.drawn(), 21 * 1000);
+    }
+
+    #[test]
+    fn spawn_columns_are_centered_leaning_left() {
+        // (board width, 3 wide column, 4 wide column)
+        for (width, three, four) in [(8, 2, 2), (10, 3, 3), (12, 4, 4)] {
+            assert_eq!(spawn_column(width, 3), three, "width {}", width);
+            assert_eq!(spawn_column(width, 4), four, "width {}", width);
+        }
+        // the guideline board is unchanged
+        assert_eq!(spawn_column(PLAYFIELD_SLOTS[0], 3), 3);
+        assert_eq!(spawn_column(PLAYFIELD_SLOTS[0], 4), 3);
+    }
+}
@@ -0,0 +1,448 @@
+use std::{fmt::Display, path::Path};
+
+use ggez::glam::IVec2;
+use strum::IntoEnumIterator;
+
+use crate::{
+    game::{GameMode, STARTING_LEVEL},
+    playfield::{PlayfieldSlots, SlotState, PLAYFIELD_SLOTS},
+    rustomino::{Direction, Rustomino, RustominoState, RustominoType},
+};
+
+const SAVE_FILE: &str = "savegame.txt";
+// every key a saved game needs besides its rows, a missing key means the save is incomplete
+const KEYS: [&str; 16] = [
+    "mode",
+    "seed",
+    "drawn",
+    "level",
+    "score",
+    "total_lines_cleared",
+    "combo",
+    "back_to_back",
+    "hold_used",
+    "holds_remaining",
+    "lockdown_resets",
+    "ground_time",
+    "play_time",
+    "next",
+    "held",
+    "active",
+];
+// far more blocks than any game draws, the bag is replayed this far when continuing
+const MAX_DRAWN: u64 = 1_000_000;
+
+/// a game left from the pause menu that can be continued later
+///
+/// saved to `savegame.txt` in the user's data directory as `key=value` lines
+/// the playfield is saved one `row=` line per row from the bottom up,
+/// `.` for empty slots, `*` for garbage and the block type for locked blocks
+/// audio and view state isn't saved, it's rebuilt when the game is continued
+#[derive(Debug, Clone)]
+pub struct SavedGame {
+    pub mode: GameMode,
+    pub seed: u64,
+    pub drawn: u64, // rustominos already drawn from the seeded bag
+    pub level: usize,
+    pub score: usize,
+    pub total_lines_cleared: usize,
+    pub combo: usize,        // blocks in a row that have cleared lines
+    pub back_to_back: usize, // tetrises and spins in a row without an easier clear
+    pub hold_used: bool,
+    pub holds_remaining: Option<u32>,
+    pub lockdown_resets: u32,
+    pub ground_time: f64,
+    pub play_time: f64,
+    pub next: Option<RustominoType>,
+    pub held: Option<RustominoType>,
+    pub active: Option<Rustomino>,
+    pub slots: PlayfieldSlots, // only locked blocks and garbage, the active block is added back
+}
+
+impl SavedGame {
+    /// returns true if there's a saved game waiting to be continued
+    pub fn exists(dir: &Path) -> bool {
+        dir.join(SAVE_FILE).is_file()
+    }
+
+    /// returns None if there's no saved game or it's from another version or invalid
+    pub fn load(dir: &Path) -> Option<SavedGame> {
+        let path = dir.join(SAVE_FILE);
+        let contents = std::fs::read_to_string(&path).ok()?;
+        let saved_game = SavedGame::parse(&contents)?;
+        log::info!("loaded saved game from {:?}", path);
+        Some(saved_game)
+    }
+
+    // reads a saved game written by Display
+    // returns None if it's from another version or any line or key is invalid or missing
+    fn parse(contents: &str) -> Option<SavedGame> {
+        let mut lines = contents.lines();
+        let version = lines.next()?.strip_prefix("version=")?;
+        if version != env!("CARGO_PKG_VERSION") {
+            log::warn!("ignoring saved game from version {}", version);
+            return None;
+        }
+
+        let mut saved_game = SavedGame {
+            mode: GameMode::Marathon,
+            seed: 0,
+            drawn: 0,
+            level: STARTING_LEVEL,
+            score: 0,
+            total_lines_cleared: 0,
+            combo: 0,
+            back_to_back: 0,
+            hold_used: false,
+            holds_remaining: None,
+            lockdown_resets: 0,
+            ground_time: 0.0,
+            play_time: 0.0,
+            next: None,
+            held: None,
+            active: None,
+            slots: [[SlotState::Empty; PLAYFIELD_SLOTS[0]]; PLAYFIELD_SLOTS[1]],
+        };
+        let mut rows = 0;
+        let mut keys = Vec::new();
+        for line in lines {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let valid = match key {
+                "row" => match parse_row(value) {
+                    Some(row) if rows < PLAYFIELD_SLOTS[1] => {
+                        saved_game.slots[rows] = row;
+                        rows += 1;
+                        true
+                    }
+                    _ => false,
+                },
+                _ => {
+                    keys.push(key);
+                    saved_game.set(key, value)
+                }
+            };
+            // a partly read game can't be continued safely
+            if !valid {
+                log::warn!("ignoring saved game with invalid line: {}", line);
+                return None;
+            }
+        }
+        if rows != PLAYFIELD_SLOTS[1] {
+            log::warn!("ignoring saved game with {} playfield rows", rows);
+            return None;
+        }
+        if let Some(key) = KEYS.iter().find(|key| !keys.contains(key)) {
+            log::warn!("ignoring saved game without {}", key);
+            return None;
+        }
+        Some(saved_game)
+    }
+
+    /// saves the game to the user's data directory, replacing any earlier save
+    pub fn save(&self, dir: &Path) {
+        let path = dir.join(SAVE_FILE);
+        match std::fs::create_dir_all(dir).and_then(|_| std::fs::write(&path, self.to_string())) {
+            Ok(_) => log::info!("saved game to {:?}", path),
+            Err(e) => log::warn!("unable to save game to {:?}: {}", path, e),
+        }
+    }
+
+    /// removes the saved game once it's been continued or found to be invalid
+    pub fn delete(dir: &Path) {
+        let path = dir.join(SAVE_FILE);
+        if let Err(e) = std::fs::remove_file(&path) {
+            log::warn!("unable to remove saved game {:?}: {}", path, e);
+        }
+    }
+
+    // sets a field from its saved key and value, returns false if either is invalid
+    fn set(&mut self, key: &str, value: &str) -> bool {
+        match key {
+            "mode" => match parse_mode(value) {
+                Some(v) => self.mode = v,
+                None => return false,
+            },
+            "seed" => match value.parse() {
+                Ok(v) => self.seed = v,
+                Err(_) => return false,
+            },
+            "drawn" => match value.parse() {
+                Ok(v) if v <= MAX_DRAWN => self.drawn = v,
+                _ => return false,
+            },
+            "level" => match value.parse() {
+                Ok(v) if v >= STARTING_LEVEL => self.level = v,
+                _ => return false,
+            },
+            "score" => match value.parse() {
+                Ok(v) => self.score = v,
+                Err(_) => return false,
+            },
+            "total_lines_cleared" => match value.parse() {
+                Ok(v) => self.total_lines_cleared = v,
+                Err(_) => return false,
+            },
+            "combo" => match value.parse() {
+                Ok(v) => self.combo = v,
+                Err(_) => return false,
+            },
+            "back_to_back" => match value.parse() {
+                Ok(v) => self.back_to_back = v,
+                Err(_) => return false,
+            },
+            "hold_used" => match value.parse() {
+                Ok(v) => self.hold_used = v,
+                Err(_) => return false,
+            },
+            "holds_remaining" if value == "None" => self.holds_remaining = None,
+            "holds_remaining" => match value.parse() {
+                Ok(v) => self.holds_remaining = Some(v),
+                Err(_) => return false,
+            },
+            "lockdown_resets" => match value.parse() {
+                Ok(v) => self.lockdown_resets = v,
+                Err(_) => return false,
+            },
+            "ground_time" => match value.parse() {
+                Ok(v) => self.ground_time = v,
+                Err(_) => return false,
+            },
+            "play_time" => match value.parse() {
+                Ok(v) => self.play_time = v,
+                Err(_) => return false,
+            },
+            "next" if value == "None" => self.next = None,
+            "next" => match parse_type(value) {
+                Some(v) => self.next = Some(v),
+                None => return false,
+            },
+            "held" if value == "None" => self.held = None,
+            "held" => match parse_type(value) {
+                Some(v) => self.held = Some(v),
+                None => return false,
+            },
+            "active" if value == "None" => self.active = None,
+            // the block has to be on the playfield to be added back to it
+            "active" => match parse_active(value).filter(on_playfield) {
+                Some(v) => self.active = Some(v),
+                None => return false,
+            },
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl Display for SavedGame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "version={}", env!("CARGO_PKG_VERSION"))?;
+        match self.mode {
+            GameMode::Trainer(rtype) => writeln!(f, "mode=Trainer {:?}", rtype)?,
+            mode => writeln!(f, "mode={:?}", mode)?,
+        }
+        writeln!(f, "seed={}", self.seed)?;
+        writeln!(f, "drawn={}", self.drawn)?;
+        writeln!(f, "level={}", self.level)?;
+        writeln!(f, "score={}", self.score)?;
+        writeln!(f, "total_lines_cleared={}", self.total_lines_cleared)?;
+        writeln!(f, "combo={}", self.combo)?;
+        writeln!(f, "back_to_back={}", self.back_to_back)?;
+        writeln!(f, "hold_used={}", self.hold_used)?;
+        match self.holds_remaining {
+            Some(holds) => writeln!(f, "holds_remaining={}", holds)?,
+            None => writeln!(f, "holds_remaining=None")?,
+        }
+        writeln!(f, "lockdown_resets={}", self.lockdown_resets)?;
+        writeln!(f, "ground_time={}", self.ground_time)?;
+        writeln!(f, "play_time={}", self.play_time)?;
+        writeln!(f, "next={:?}", OptionalType(self.next))?;
+        writeln!(f, "held={:?}", OptionalType(self.held))?;
+        match &self.active {
+            // type, facing, block offsets, translation and the falling or lockdown timer
+            Some(active) => {
+                let (state, time) = match active.state {
+                    RustominoState::Falling { time } => ("Falling", time),
+                    RustominoState::Lockdown { time } => ("Lockdown", time),
+                };
+                write!(
+                    f,
+                    "active={:?} {:?}",
+                    active.rtype,
+                    active.rotation.direction()
+                )?;
+                for block in active.blocks.iter().chain([&active.translation]) {
+                    write!(f, " {},{}", block.x, block.y)?;
+                }
+                writeln!(f, " {} {}", state, time)?;
+            }
+            None => writeln!(f, "active=None")?,
+        }
+        for row in self.slots.iter() {
+            let row: String = row.iter().map(|slot| slot_char(*slot)).collect();
+            writeln!(f, "row={}", row)?;
+        }
+        Ok(())
+    }
+}
+
+// writes a block type or None without the Some wrapper
+struct OptionalType(Option<RustominoType>);
+
+impl std::fmt::Debug for OptionalType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Some(rtype) => write!(f, "{:?}", rtype),
+            None => write!(f, "None"),
+        }
+    }
+}
+
+pub fn parse_type(value: &str) -> Option<RustominoType> {
+    RustominoType::iter().find(|r| format!("{:?}", r) == value)
+}
+
+fn parse_mode(value: &str) -> Option<GameMode> {
+    match value.split_once(' ') {
+        Some(("Trainer", rtype)) => Some(GameMode::Trainer(parse_type(rtype)?)),
+        None if value == "Marathon" => Some(GameMode::Marathon),
+        // demos aren't saved
+        _ => None,
+    }
+}
+
+fn parse_active(value: &str) -> Option<Rustomino> {
+    let mut parts = value.split_whitespace();
+    let rtype = parse_type(parts.next()?)?;
+    let direction = parts.next()?;
+    let direction = Direction::iter().find(|d| format!("{:?}", d) == direction)?;
+    let mut rustomino = Rustomino::new(rtype).facing(direction);
+    for block in rustomino.blocks.iter_mut() {
+        *block = parse_ivec2(parts.next()?)?;
+    }
+    rustomino.translation = parse_ivec2(parts.next()?)?;
+    let state = parts.next()?;
+    let time = parts.next()?.parse().ok()?;
+    rustomino.state = match state {
+        "Falling" => RustominoState::Falling { time },
+        "Lockdown" => RustominoState::Lockdown { time },
+        _ => return None,
+    };
+    if parts.next().is_some() {
+        return None;
+    }
+    Some(rustomino)
+}
+
+fn on_playfield(rustomino: &Rustomino) -> bool {
+    rustomino.playfield_slots().iter().all(|slot| {
+        (0..PLAYFIELD_SLOTS[0] as i32).contains(&slot.x)
+            && (0..PLAYFIELD_SLOTS[1] as i32).contains(&slot.y)
+    })
+}
+
+fn parse_ivec2(value: &str) -> Option<IVec2> {
+    let (x, y) = value.split_once(',')?;
+    Some(IVec2::new(x.parse().ok()?, y.parse().ok()?))
+}
+
+fn slot_char(slot: SlotState) -> char {
+    match slot {
+        SlotState::Locked(rtype) => format!("{:?}", rtype).chars().next().unwrap_or('.'),
+        SlotState::Garbage => '*',
+        // the active block and its ghost are added back when the game is continued
+        SlotState::Empty | SlotState::Occupied(_) | SlotState::Ghost(_) => '.',
+    }
+}
+
+pub fn parse_row(value: &str) -> Option<[SlotState; PLAYFIELD_SLOTS[0]]> {
+    let mut row = [SlotState::Empty; PLAYFIELD_SLOTS[0]];
+    if value.chars().count() != row.len() {
+        return None;
+    }
+    for (slot, c) in row.iter_mut().zip(value.chars()) {
+        *slot = match c {
+            '.' => SlotState::Empty,
+            '*' => SlotState::Garbage,
+            c => SlotState::Locked(parse_type(&c.to_string())?),
+        };
+    }
+    Some(row)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn saved_game() -> SavedGame {
+        let mut slots = [[SlotState::Empty; PLAYFIELD_SLOTS[0]]; PLAYFIELD_SLOTS[1]];
+        slots[0][0] = SlotState::Garbage;
+        slots[0][1] = SlotState::Locked(RustominoType::S);
+        SavedGame {
+            mode: GameMode::Trainer(RustominoType::T),
+            seed: 42,
+            drawn: 42,
+            level: 3,
+            score: 12345,
+            total_lines_cleared: 25,
+            combo: 3,
+            back_to_back: 2,
+            hold_used: true,
+            holds_remaining: Some(2),
+            lockdown_resets: 4,
+            ground_time: 0.25,
+            play_time: 321.5,
+            next: Some(RustominoType::I),
+            held: None,
+            active: Some(Rustomino::new(RustominoType::L).facing(Direction::E)),
+            slots,
+        }
+    }
+
+    // the saved game's text with the line starting with key replaced
+    fn with_line(key: &str, line: Option<&str>) -> String {
+        saved_game()
+            .to_string()
+            .lines()
+            .filter_map(|l| if l.starts_with(key) { line } else { Some(l) })
+            .map(|l| format!("{}\n", l))
+            .collect()
+    }
+
+    #[test]
+    fn round_trip() {
+        let loaded = SavedGame::parse(&saved_game().to_string()).unwrap();
+        assert_eq!(loaded.to_string(), saved_game().to_string());
+        assert_eq!(loaded.slots, saved_game().slots);
+        assert_eq!(loaded.combo, 3);
+        assert_eq!(loaded.back_to_back, 2);
+    }
+
+    #[test]
+    fn every_key_is_required() {
+        for key in KEYS {
+            let contents = with_line(&format!("{}=", key), None);
+            assert!(SavedGame::parse(&contents).is_none(), "without {}", key);
+        }
+    }
+
+    #[test]
+    fn rejects_a_level_below_the_starting_level() {
+        assert!(SavedGame::parse(&with_line("level=", Some("level=0"))).is_none());
+        let level = format!("level={}", STARTING_LEVEL);
+        assert!(SavedGame::parse(&with_line("level=", Some(&level))).is_some());
+    }
+
+    #[test]
+    fn rejects_more_draws_than_a_game_makes() {
+        let drawn = format!("drawn={}", u64::MAX);
+        assert!(SavedGame::parse(&with_line("drawn=", Some(&drawn))).is_none());
+    }
+
+    #[test]
+    fn rejects_an_active_block_off_the_playfield() {
+        let active = "active=L E 1,0 1,1 1,2 2,0 -5,0 Falling 0";
+        assert!(SavedGame::parse(&with_line("active=", Some(active))).is_none());
+    }
+}
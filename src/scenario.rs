@@ -0,0 +1,126 @@
+use std::path::{Path, PathBuf};
+
+use crate::{
+    board_code,
+    playfield::{PlayfieldSlots, SlotState, PLAYFIELD_SLOTS},
+    rustomino::RustominoType,
+    savegame::{parse_row, parse_type},
+};
+
+const SCENARIO_DIR: &str = "scenarios";
+const SCENARIO_EXTENSION: &str = "txt";
+
+/// a position to practice from, loaded from the scenarios folder
+/// in the user's data directory
+///
+/// scenario files are `key=value` lines, lines starting with `#` are comments
+/// `active` is the block to play and `next` and `held` are optional,
+/// `next` lists the blocks dealt before the bag takes over, separated by spaces
+/// the board is either a `board=` code from a snapshot or `row=` lines drawn
+/// top down like the playfield looks, `.` for empty slots, `*` for garbage
+/// and the block type for locked blocks, rows left out above them are empty
+#[derive(Debug, Clone)]
+pub struct Scenario {
+    pub name: String,
+    pub active: RustominoType,
+    pub next: Vec<RustominoType>,
+    pub held: Option<RustominoType>,
+    pub slots: PlayfieldSlots,
+}
+
+impl Scenario {
+    /// the names of the scenarios in the user's data directory, sorted
+    pub fn list(dir: &Path) -> Vec<String> {
+        let path = scenario_dir(dir);
+        let entries = match std::fs::read_dir(&path) {
+            Ok(entries) => entries,
+            Err(e) => {
+                log::info!("unable to read scenarios from {:?}: {}", path, e);
+                return Vec::new();
+            }
+        };
+        let mut names = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|e| e == SCENARIO_EXTENSION))
+            .filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    /// returns None if the scenario can't be read or is invalid
+    pub fn load(dir: &Path, name: &str) -> Option<Scenario> {
+        let path = scenario_dir(dir).join(format!("{}.{}", name, SCENARIO_EXTENSION));
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                log::warn!("unable to read scenario {:?}: {}", path, e);
+                return None;
+            }
+        };
+
+        let mut active = None;
+        let mut next = Vec::new();
+        let mut held = None;
+        let mut board = None;
+        let mut rows = Vec::new();
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let valid = match line.split_once('=') {
+                Some(("active", value)) => parse_type(value).map(|v| active = Some(v)),
+                Some(("held", value)) => parse_type(value).map(|v| held = Some(v)),
+                Some(("next", value)) => value
+                    .split_whitespace()
+                    .map(parse_type)
+                    .collect::<Option<Vec<_>>>()
+                    .map(|v| next = v),
+                Some(("board", value)) => board_code::decode(value).map(|v| board = Some(v)),
+                Some(("row", value)) => parse_row(value).map(|v| rows.push(v)),
+                _ => None,
+            };
+            if valid.is_none() {
+                log::warn!("ignoring scenario {:?} with invalid line: {}", path, line);
+                return None;
+            }
+        }
+
+        let Some(active) = active else {
+            log::warn!("ignoring scenario {:?} without an active block", path);
+            return None;
+        };
+        let slots = match board {
+            Some(slots) if rows.is_empty() => slots,
+            Some(_) => {
+                log::warn!("ignoring scenario {:?} with both a board and rows", path);
+                return None;
+            }
+            None if rows.len() > PLAYFIELD_SLOTS[1] => {
+                log::warn!("ignoring scenario {:?} with {} rows", path, rows.len());
+                return None;
+            }
+            // the last row drawn is the bottom of the playfield
+            None => {
+                let mut slots = [[SlotState::Empty; PLAYFIELD_SLOTS[0]]; PLAYFIELD_SLOTS[1]];
+                for (slot_row, row) in slots.iter_mut().zip(rows.into_iter().rev()) {
+                    *slot_row = row;
+                }
+                slots
+            }
+        };
+        log::info!("loaded scenario {:?}", path);
+        Some(Scenario {
+            name: name.to_string(),
+            active,
+            next,
+            held,
+            slots,
+        })
+    }
+}
+
+fn scenario_dir(dir: &Path) -> PathBuf {
+    dir.join(SCENARIO_DIR)
+}
@@ -0,0 +1,78 @@
+use crate::{playfield::TSpin, settings::Scoring};
+
+// guideline scoring, by the number of lines cleared
+const LINE_SCORES: [usize; 5] = [0, 100, 300, 500, 800];
+const MINI_T_SPIN_SCORES: [usize; 3] = [100, 200, 400];
+const T_SPIN_SCORES: [usize; 4] = [400, 800, 1200, 1600];
+const COMBO_STEP: usize = 50;
+const BACK_TO_BACK_MULTIPLIER: f64 = 1.5;
+const SOFT_DROP_ROW_SCORE: usize = 1;
+const HARD_DROP_ROW_SCORE: usize = 2;
+// classic (NES) scoring
+const CLASSIC_LINE_SCORES: [usize; 5] = [0, 40, 100, 300, 1200];
+const CLASSIC_SOFT_DROP_ROW_SCORE: usize = 1; // the NES push down points
+
+/// the points awarded for clears and drops
+/// clear and combo points are multiplied by the level, drop points aren't
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoringConfig {
+    pub lines: [usize; 5],            // by the number of lines cleared
+    pub mini_t_spins: [usize; 3],     // by the number of lines cleared
+    pub t_spins: [usize; 4],          // by the number of lines cleared
+    pub spins: bool,                  // t-spins and all spins are recognised
+    pub combo_step: usize,            // added for each clear in a row after the first
+    pub back_to_back_multiplier: f64, // applied to a tetris or spin clear straight after another
+    pub soft_drop_row: usize,         // for each row moved by soft drop
+    pub hard_drop_row: usize,         // for each row moved by hard drop
+}
+
+impl ScoringConfig {
+    pub fn guideline() -> Self {
+        Self {
+            lines: LINE_SCORES,
+            mini_t_spins: MINI_T_SPIN_SCORES,
+            t_spins: T_SPIN_SCORES,
+            spins: true,
+            combo_step: COMBO_STEP,
+            back_to_back_multiplier: BACK_TO_BACK_MULTIPLIER,
+            soft_drop_row: SOFT_DROP_ROW_SCORE,
+            hard_drop_row: HARD_DROP_ROW_SCORE,
+        }
+    }
+
+    /// the number of lines and soft dropped rows count, there are no bonuses
+    /// the NES had no hard drop so it scores nothing
+    pub fn classic() -> Self {
+        Self {
+            lines: CLASSIC_LINE_SCORES,
+            mini_t_spins: [0; 3],
+            t_spins: [0; 4],
+            spins: false,
+            combo_step: 0,
+            back_to_back_multiplier: 1.0,
+            soft_drop_row: CLASSIC_SOFT_DROP_ROW_SCORE,
+            hard_drop_row: 0,
+        }
+    }
+
+    pub fn for_scoring(scoring: Scoring) -> Self {
+        match scoring {
+            Scoring::Guideline => Self::guideline(),
+            Scoring::Classic => Self::classic(),
+        }
+    }
+
+    /// the points for a clear before the level multiplier
+    /// spins that clear more lines than their table has score as plain clears
+    pub fn clear(&self, num_lines: usize, t_spin: TSpin) -> usize {
+        let spin_scores: &[usize] = match t_spin {
+            TSpin::None => &[],
+            TSpin::Mini => &self.mini_t_spins,
+            TSpin::Full => &self.t_spins,
+        };
+        match spin_scores.get(num_lines).or(self.lines.get(num_lines)) {
+            Some(score) => *score,
+            None => panic!("impossible number of lines cleared"),
+        }
+    }
+}
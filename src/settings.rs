@@ -0,0 +1,637 @@
+// default gameplay settings
+const LOCKDOWN_DELAY: f64 = 0.5; // how long to wait before locking block (Tetris Guideline)
+const LOCKDOWN_MAX_RESETS: u32 = 15; // maximum number of times the lockdown timer can be reset (Tetris Guideline)
+const MAX_GROUND_TIME: f64 = 10.0; // generous so only deliberate stalling is cut short
+const DANGER_ROWS: usize = 3; // how close the stack gets to the top before the danger warning
+const CAPTURE_DIR: &str = "captures";
+const DAS: f64 = 0.3; // how long a direction is held before it starts repeating
+const ARR: f64 = 0.025; // time between repeats once a held direction is repeating
+const SOFT_DROP_DAS: f64 = 0.2;
+const SOFT_DROP_ARR: f64 = 0.03;
+const SPAWN_DELAY: f64 = 0.1; // a short pause so a new block can be seen before it falls
+
+use std::path::Path;
+
+use strum::{EnumIter, IntoEnumIterator};
+
+use crate::{
+    rustomino::RustominoType,
+    theme::{BackgroundColor, ThemeName},
+};
+
+const SETTINGS_FILE: &str = "settings.txt";
+
+// gameplay setting limits
+pub const LOCKDOWN_DELAY_MIN: f64 = 0.0;
+pub const LOCKDOWN_DELAY_MAX: f64 = 2.0;
+pub const LOCKDOWN_DELAY_STEP: f64 = 0.05;
+pub const LOCKDOWN_MAX_RESETS_MAX: u32 = 30;
+pub const MAX_GROUND_TIME_MAX: f64 = 30.0;
+pub const MAX_GROUND_TIME_STEP: f64 = 1.0;
+pub const DANGER_ROWS_MAX: usize = 8;
+pub const METRONOME_BPM_MAX: u32 = 240;
+pub const SPEED_CAP_MAX: usize = 30;
+pub const SPAWN_DELAY_MAX: f64 = 0.5;
+pub const SPAWN_DELAY_STEP: f64 = 0.05;
+pub const METRONOME_BPM_STEP: u32 = 10;
+pub const HOLD_LIMIT_MAX: u32 = 20;
+pub const DAS_MAX: f64 = 0.5;
+pub const DAS_STEP: f64 = 0.01;
+pub const ARR_MAX: f64 = 0.1;
+pub const ARR_STEP: f64 = 0.005; // an ARR of 0 repeats every update
+
+#[derive(Debug, Clone)]
+pub struct Settings {
+    pub lockdown_delay: f64, // seconds a grounded block waits before locking
+    pub spawn_delay: f64,    // seconds a new block waits before gravity applies
+    pub lockdown_max_resets: u32,
+    pub max_ground_time: f64, // seconds a block can spend on the ground before it's forced to lock, 0 is off
+    pub reduce_motion: bool,  // replace animations with static alternatives
+    pub gravity_curve: GravityCurve,
+    pub speed_cap: usize, // the level gravity stops speeding up at, 0 is no cap
+    pub lockdown_reset: LockdownReset,
+    pub scoring: Scoring,
+    pub all_spin: bool, // any block rotated into an immobile spot scores a spin
+    pub show_buffer_rows: bool, // show the two rows above the playfield where blocks spawn
+    pub hide_staging: bool, // show only the 10x20 playfield, blocks spawn from its top edge
+    pub infinite_hold: bool, // allow hold to be used more than once per block
+    pub hold_limit: u32, // total holds allowed, 0 is unlimited
+    pub hold_limit_scope: HoldLimitScope,
+    pub hard_drop_lock: bool,   // lock a block as soon as it's hard dropped
+    pub lock_on_contact: bool,  // lock a block as soon as it lands, skipping lockdown entirely
+    pub spawn_flat_up: bool,    // spawn T, L and J blocks flat side up
+    pub das_carry: bool,        // keep held directions charged when the next block spawns
+    pub sticky_rotation: bool,  // retry a blocked rotation briefly until it fits
+    pub show_drop_hint: bool,   // show how far a hard drop would move the block on the ghost
+    pub show_spawn_guide: bool, // mark the columns the next block will spawn in
+    pub show_drought: bool,     // show how many blocks have been drawn since the last I block
+    pub show_lock_timer: bool,  // show the lockdown time and resets left beside the playfield
+    pub show_heatmap: bool,     // shade the slots blocks have been locked in this game
+    pub colored_ghost: bool,    // draw the ghost in the active block's color instead of gray
+    pub mono_blocks: bool,      // draw every block in one high contrast color
+    pub show_frame: bool,       // draw a frame around the playfield
+    pub theme: ThemeName,
+    pub background_color: BackgroundColor,
+    pub hud_scale: HudScale,
+    pub soft_drop_speed: SoftDropSpeed,
+    pub key_layout: KeyLayout,
+    pub swap_drop_keys: bool, // soft drop on the hard drop keys and the other way around
+    pub das: f64,             // seconds left and right are held before they repeat
+    pub arr: f64,             // seconds between left and right repeats
+    pub soft_drop_das: f64,   // soft drop DAS when it is set to Repeat
+    pub soft_drop_arr: f64,   // soft drop ARR when it is set to Repeat
+    pub lock_pitch_variation: bool, // raise the lock sound's pitch with the stack height
+    pub danger_rows: usize,   // rows from the top where the danger warning starts, 0 is off
+    pub danger_sound: bool,   // play a warning sound while the stack is in danger
+    pub resets_exhausted_sound: bool, // play a sound when running out of resets forces a lock
+    pub metronome_bpm: u32,   // beats per minute of the practice metronome, 0 is off
+    pub trainer_block: Option<RustominoType>, // practice with only this block type
+    pub confirm_quit: bool,   // ask before quitting from the main menu
+    pub last_mode: LastMode,  // the mode last started from the main menu
+    pub capture_dir: String,  // where debug frame captures are saved
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            lockdown_delay: LOCKDOWN_DELAY,
+            spawn_delay: SPAWN_DELAY,
+            lockdown_max_resets: LOCKDOWN_MAX_RESETS,
+            max_ground_time: MAX_GROUND_TIME,
+            reduce_motion: false,
+            gravity_curve: GravityCurve::Logarithmic,
+            speed_cap: 0,
+            lockdown_reset: LockdownReset::Move,
+            scoring: Scoring::Guideline,
+            all_spin: false,
+            show_buffer_rows: false,
+            hide_staging: false,
+            infinite_hold: false,
+            hold_limit: 0,
+            hold_limit_scope: HoldLimitScope::Game,
+            hard_drop_lock: true,
+            lock_on_contact: false,
+            spawn_flat_up: false,
+            das_carry: true,
+            sticky_rotation: false,
+            lock_pitch_variation: true,
+            danger_rows: DANGER_ROWS,
+            danger_sound: false,
+            resets_exhausted_sound: true,
+            metronome_bpm: 0,
+            show_drop_hint: false,
+            show_spawn_guide: false,
+            show_drought: false,
+            show_lock_timer: false,
+            show_heatmap: false,
+            colored_ghost: true,
+            mono_blocks: false,
+            show_frame: false,
+            theme: ThemeName::Classic,
+            background_color: BackgroundColor::Theme,
+            hud_scale: HudScale::Normal,
+            soft_drop_speed: SoftDropSpeed::Fast,
+            key_layout: KeyLayout::Standard,
+            swap_drop_keys: false,
+            das: DAS,
+            arr: ARR,
+            soft_drop_das: SOFT_DROP_DAS,
+            soft_drop_arr: SOFT_DROP_ARR,
+            trainer_block: None,
+            confirm_quit: true,
+            last_mode: LastMode::Marathon,
+            capture_dir: CAPTURE_DIR.to_string(),
+        }
+    }
+}
+
+impl Settings {
+    /// loads the settings from the user's data directory,
+    /// falling back to the defaults for anything missing or invalid
+    pub fn load(dir: &Path) -> Settings {
+        let path = dir.join(SETTINGS_FILE);
+        let mut settings = Settings::default();
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                log::info!("unable to read settings from {:?}: {}", path, e);
+                return settings;
+            }
+        };
+        for line in contents.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            if !settings.set(key.trim(), value.trim()) {
+                log::warn!("ignoring invalid setting: {}", line);
+            }
+        }
+        log::info!("loaded settings from {:?}", path);
+        settings
+    }
+
+    /// saves the settings to the user's data directory
+    pub fn save(&self, dir: &Path) {
+        let path = dir.join(SETTINGS_FILE);
+        match std::fs::create_dir_all(dir).and_then(|_| std::fs::write(&path, self.to_string())) {
+            Ok(_) => log::info!("saved settings to {:?}", path),
+            Err(e) => log::warn!("unable to save settings to {:?}: {}", path, e),
+        }
+    }
+
+    // sets a setting from its saved key and value, returns false if either is invalid
+    fn set(&mut self, key: &str, value: &str) -> bool {
+        match key {
+            "lockdown_delay" => match value.parse::<f64>() {
+                Ok(v) => self.lockdown_delay = v.clamp(LOCKDOWN_DELAY_MIN, LOCKDOWN_DELAY_MAX),
+                Err(_) => return false,
+            },
+            "spawn_delay" => match value.parse::<f64>() {
+                Ok(v) => self.spawn_delay = v.clamp(0.0, SPAWN_DELAY_MAX),
+                Err(_) => return false,
+            },
+            "das" => match value.parse::<f64>() {
+                Ok(v) => self.das = v.clamp(0.0, DAS_MAX),
+                Err(_) => return false,
+            },
+            "arr" => match value.parse::<f64>() {
+                Ok(v) => self.arr = v.clamp(0.0, ARR_MAX),
+                Err(_) => return false,
+            },
+            "soft_drop_das" => match value.parse::<f64>() {
+                Ok(v) => self.soft_drop_das = v.clamp(0.0, DAS_MAX),
+                Err(_) => return false,
+            },
+            "soft_drop_arr" => match value.parse::<f64>() {
+                Ok(v) => self.soft_drop_arr = v.clamp(0.0, ARR_MAX),
+                Err(_) => return false,
+            },
+            "lockdown_max_resets" => match value.parse::<u32>() {
+                Ok(v) => self.lockdown_max_resets = v.min(LOCKDOWN_MAX_RESETS_MAX),
+                Err(_) => return false,
+            },
+            "max_ground_time" => match value.parse::<f64>() {
+                Ok(v) => self.max_ground_time = v.clamp(0.0, MAX_GROUND_TIME_MAX),
+                Err(_) => return false,
+            },
+            "reduce_motion" => match value.parse() {
+                Ok(v) => self.reduce_motion = v,
+                Err(_) => return false,
+            },
+            "speed_cap" => match value.parse::<usize>() {
+                Ok(v) => self.speed_cap = v.min(SPEED_CAP_MAX),
+                Err(_) => return false,
+            },
+            "gravity_curve" => match GravityCurve::iter().find(|c| format!("{:?}", c) == value) {
+                Some(v) => self.gravity_curve = v,
+                None => return false,
+            },
+            "scoring" => match Scoring::iter().find(|s| format!("{:?}", s) == value) {
+                Some(v) => self.scoring = v,
+                None => return false,
+            },
+            "all_spin" => match value.parse() {
+                Ok(v) => self.all_spin = v,
+                Err(_) => return false,
+            },
+            "lockdown_reset" => match LockdownReset::iter().find(|r| format!("{:?}", r) == value) {
+                Some(v) => self.lockdown_reset = v,
+                None => return false,
+            },
+            "show_buffer_rows" => match value.parse() {
+                Ok(v) => self.show_buffer_rows = v,
+                Err(_) => return false,
+            },
+            "hide_staging" => match value.parse() {
+                Ok(v) => self.hide_staging = v,
+                Err(_) => return false,
+            },
+            "infinite_hold" => match value.parse() {
+                Ok(v) => self.infinite_hold = v,
+                Err(_) => return false,
+            },
+            "confirm_quit" => match value.parse() {
+                Ok(v) => self.confirm_quit = v,
+                Err(_) => return false,
+            },
+            // unknown modes are ignored so they fall back to marathon
+            "last_mode" => match LastMode::iter().find(|m| format!("{:?}", m) == value) {
+                Some(v) => self.last_mode = v,
+                None => return false,
+            },
+            "capture_dir" if !value.is_empty() => self.capture_dir = value.to_string(),
+            "hold_limit" => match value.parse::<u32>() {
+                Ok(v) => self.hold_limit = v.min(HOLD_LIMIT_MAX),
+                Err(_) => return false,
+            },
+            "hold_limit_scope" => {
+                match HoldLimitScope::iter().find(|s| format!("{:?}", s) == value) {
+                    Some(v) => self.hold_limit_scope = v,
+                    None => return false,
+                }
+            }
+            "hard_drop_lock" => match value.parse() {
+                Ok(v) => self.hard_drop_lock = v,
+                Err(_) => return false,
+            },
+            "lock_on_contact" => match value.parse() {
+                Ok(v) => self.lock_on_contact = v,
+                Err(_) => return false,
+            },
+            "resets_exhausted_sound" => match value.parse() {
+                Ok(v) => self.resets_exhausted_sound = v,
+                Err(_) => return false,
+            },
+            "metronome_bpm" => match value.parse::<u32>() {
+                Ok(v) => self.metronome_bpm = v.min(METRONOME_BPM_MAX),
+                Err(_) => return false,
+            },
+            "danger_rows" => match value.parse::<usize>() {
+                Ok(v) => self.danger_rows = v.min(DANGER_ROWS_MAX),
+                Err(_) => return false,
+            },
+            "danger_sound" => match value.parse() {
+                Ok(v) => self.danger_sound = v,
+                Err(_) => return false,
+            },
+            "lock_pitch_variation" => match value.parse() {
+                Ok(v) => self.lock_pitch_variation = v,
+                Err(_) => return false,
+            },
+            "das_carry" => match value.parse() {
+                Ok(v) => self.das_carry = v,
+                Err(_) => return false,
+            },
+            "sticky_rotation" => match value.parse() {
+                Ok(v) => self.sticky_rotation = v,
+                Err(_) => return false,
+            },
+            "spawn_flat_up" => match value.parse() {
+                Ok(v) => self.spawn_flat_up = v,
+                Err(_) => return false,
+            },
+            "show_frame" => match value.parse() {
+                Ok(v) => self.show_frame = v,
+                Err(_) => return false,
+            },
+            "colored_ghost" => match value.parse() {
+                Ok(v) => self.colored_ghost = v,
+                Err(_) => return false,
+            },
+            "mono_blocks" => match value.parse() {
+                Ok(v) => self.mono_blocks = v,
+                Err(_) => return false,
+            },
+            "show_spawn_guide" => match value.parse() {
+                Ok(v) => self.show_spawn_guide = v,
+                Err(_) => return false,
+            },
+            "show_drought" => match value.parse() {
+                Ok(v) => self.show_drought = v,
+                Err(_) => return false,
+            },
+            "show_lock_timer" => match value.parse() {
+                Ok(v) => self.show_lock_timer = v,
+                Err(_) => return false,
+            },
+            "show_heatmap" => match value.parse() {
+                Ok(v) => self.show_heatmap = v,
+                Err(_) => return false,
+            },
+            "show_drop_hint" => match value.parse() {
+                Ok(v) => self.show_drop_hint = v,
+                Err(_) => return false,
+            },
+            "hud_scale" => match HudScale::iter().find(|h| format!("{:?}", h) == value) {
+                Some(v) => self.hud_scale = v,
+                None => return false,
+            },
+            "soft_drop_speed" => {
+                match SoftDropSpeed::iter().find(|s| format!("{:?}", s) == value) {
+                    Some(v) => self.soft_drop_speed = v,
+                    None => return false,
+                }
+            }
+            "key_layout" => match KeyLayout::iter().find(|l| format!("{:?}", l) == value) {
+                Some(v) => self.key_layout = v,
+                None => return false,
+            },
+            "swap_drop_keys" => match value.parse() {
+                Ok(v) => self.swap_drop_keys = v,
+                Err(_) => return false,
+            },
+            "trainer_block" => match RustominoType::iter().find(|r| format!("{:?}", r) == value) {
+                Some(v) => self.trainer_block = Some(v),
+                None if value == "Off" => self.trainer_block = None,
+                None => return false,
+            },
+            "theme" => match ThemeName::iter().find(|t| format!("{:?}", t) == value) {
+                Some(v) => self.theme = v,
+                None => return false,
+            },
+            "background_color" => {
+                match BackgroundColor::iter().find(|c| format!("{:?}", c) == value) {
+                    Some(v) => self.background_color = v,
+                    None => return false,
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl std::fmt::Display for Settings {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "lockdown_delay={}", self.lockdown_delay)?;
+        writeln!(f, "spawn_delay={}", self.spawn_delay)?;
+        writeln!(f, "lockdown_max_resets={}", self.lockdown_max_resets)?;
+        writeln!(f, "max_ground_time={}", self.max_ground_time)?;
+        writeln!(f, "reduce_motion={}", self.reduce_motion)?;
+        writeln!(f, "gravity_curve={:?}", self.gravity_curve)?;
+        writeln!(f, "speed_cap={}", self.speed_cap)?;
+        writeln!(f, "lockdown_reset={:?}", self.lockdown_reset)?;
+        writeln!(f, "scoring={:?}", self.scoring)?;
+        writeln!(f, "all_spin={}", self.all_spin)?;
+        writeln!(f, "show_buffer_rows={}", self.show_buffer_rows)?;
+        writeln!(f, "hide_staging={}", self.hide_staging)?;
+        writeln!(f, "infinite_hold={}", self.infinite_hold)?;
+        writeln!(f, "hold_limit={}", self.hold_limit)?;
+        writeln!(f, "hold_limit_scope={:?}", self.hold_limit_scope)?;
+        writeln!(f, "hard_drop_lock={}", self.hard_drop_lock)?;
+        writeln!(f, "lock_on_contact={}", self.lock_on_contact)?;
+        writeln!(f, "spawn_flat_up={}", self.spawn_flat_up)?;
+        writeln!(f, "das_carry={}", self.das_carry)?;
+        writeln!(f, "sticky_rotation={}", self.sticky_rotation)?;
+        writeln!(f, "lock_pitch_variation={}", self.lock_pitch_variation)?;
+        writeln!(f, "danger_rows={}", self.danger_rows)?;
+        writeln!(f, "danger_sound={}", self.danger_sound)?;
+        writeln!(f, "resets_exhausted_sound={}", self.resets_exhausted_sound)?;
+        writeln!(f, "metronome_bpm={}", self.metronome_bpm)?;
+        writeln!(f, "show_drop_hint={}", self.show_drop_hint)?;
+        writeln!(f, "show_spawn_guide={}", self.show_spawn_guide)?;
+        writeln!(f, "show_drought={}", self.show_drought)?;
+        writeln!(f, "show_lock_timer={}", self.show_lock_timer)?;
+        writeln!(f, "show_heatmap={}", self.show_heatmap)?;
+        writeln!(f, "colored_ghost={}", self.colored_ghost)?;
+        writeln!(f, "mono_blocks={}", self.mono_blocks)?;
+        writeln!(f, "show_frame={}", self.show_frame)?;
+        writeln!(f, "theme={:?}", self.theme)?;
+        writeln!(f, "background_color={:?}", self.background_color)?;
+        writeln!(f, "hud_scale={:?}", self.hud_scale)?;
+        writeln!(f, "soft_drop_speed={:?}", self.soft_drop_speed)?;
+        writeln!(f, "key_layout={:?}", self.key_layout)?;
+        writeln!(f, "swap_drop_keys={}", self.swap_drop_keys)?;
+        writeln!(f, "das={}", self.das)?;
+        writeln!(f, "arr={}", self.arr)?;
+        writeln!(f, "soft_drop_das={}", self.soft_drop_das)?;
+        writeln!(f, "soft_drop_arr={}", self.soft_drop_arr)?;
+        match self.trainer_block {
+            Some(rtype) => writeln!(f, "trainer_block={:?}", rtype)?,
+            None => writeln!(f, "trainer_block=Off")?,
+        }
+        writeln!(f, "confirm_quit={}", self.confirm_quit)?;
+        writeln!(f, "last_mode={:?}", self.last_mode)?;
+        writeln!(f, "capture_dir={}", self.capture_dir)
+    }
+}
+
+/// the curve used to calculate the gravity delay for a level
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter)]
+pub enum GravityCurve {
+    Logarithmic,
+    Guideline,
+}
+
+impl GravityCurve {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GravityCurve::Logarithmic => "Log",
+            GravityCurve::Guideline => "Guideline",
+        }
+    }
+
+    /// returns the next curve, wrapping around to the first
+    pub fn next(&self) -> GravityCurve {
+        let curves = GravityCurve::iter().collect::<Vec<_>>();
+        let i = curves.iter().position(|c| c == self).unwrap_or(0);
+        curves[(i + 1) % curves.len()]
+    }
+}
+
+/// what resets the lockdown timer of a grounded block
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter)]
+pub enum LockdownReset {
+    Move, // moving or rotating resets the timer, up to the maximum number of resets
+    Step, // the timer only resets when the block moves down a row
+}
+
+impl LockdownReset {
+    pub fn name(&self) -> &'static str {
+        match self {
+            LockdownReset::Move => "Move",
+            LockdownReset::Step => "Step",
+        }
+    }
+
+    pub fn toggled(&self) -> LockdownReset {
+        match self {
+            LockdownReset::Move => LockdownReset::Step,
+            LockdownReset::Step => LockdownReset::Move,
+        }
+    }
+}
+
+/// how cleared lines are scored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter)]
+pub enum Scoring {
+    Guideline, // t-spins score on top of line clears
+    Classic,   // NES style, only lines and soft drops count
+}
+
+impl Scoring {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Scoring::Guideline => "Guideline",
+            Scoring::Classic => "Classic",
+        }
+    }
+
+    pub fn toggled(&self) -> Scoring {
+        match self {
+            Scoring::Guideline => Scoring::Classic,
+            Scoring::Classic => Scoring::Guideline,
+        }
+    }
+}
+
+/// the modes that can be started from the main menu
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter)]
+pub enum LastMode {
+    Marathon,
+    Demo,
+}
+
+/// when the hold limit is refilled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter)]
+pub enum HoldLimitScope {
+    Game,  // the limit covers the whole game
+    Level, // the limit is refilled at each new level
+}
+
+impl HoldLimitScope {
+    pub fn name(&self) -> &'static str {
+        match self {
+            HoldLimitScope::Game => "Per Game",
+            HoldLimitScope::Level => "Per Level",
+        }
+    }
+
+    pub fn toggled(&self) -> HoldLimitScope {
+        match self {
+            HoldLimitScope::Game => HoldLimitScope::Level,
+            HoldLimitScope::Level => HoldLimitScope::Game,
+        }
+    }
+}
+
+/// how large the hud text is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter)]
+pub enum HudScale {
+    Normal,
+    Large,
+    Larger,
+}
+
+impl HudScale {
+    pub fn name(&self) -> &'static str {
+        match self {
+            HudScale::Normal => "100%",
+            HudScale::Large => "125%",
+            HudScale::Larger => "150%",
+        }
+    }
+
+    pub fn scale(&self) -> f32 {
+        match self {
+            HudScale::Normal => 1.0,
+            HudScale::Large => 1.25,
+            HudScale::Larger => 1.5,
+        }
+    }
+
+    /// returns the next scale, wrapping around to the first
+    pub fn next(&self) -> HudScale {
+        let scales = HudScale::iter().collect::<Vec<_>>();
+        let i = scales.iter().position(|s| s == self).unwrap_or(0);
+        scales[(i + 1) % scales.len()]
+    }
+}
+
+/// how much faster gravity is while soft drop is held
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter)]
+pub enum SoftDropSpeed {
+    Slow,
+    Fast,
+    Instant, // falls to the bottom in one tick, but still doesn't lock
+    Repeat,  // steps down on its own DAS and ARR like a held direction
+}
+
+impl SoftDropSpeed {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SoftDropSpeed::Slow => "6x",
+            SoftDropSpeed::Fast => "20x",
+            SoftDropSpeed::Instant => "Instant",
+            SoftDropSpeed::Repeat => "Repeat",
+        }
+    }
+
+    /// the gravity multiplier, None for an instant drop
+    pub fn factor(&self) -> Option<f64> {
+        match self {
+            SoftDropSpeed::Slow => Some(6.0),
+            SoftDropSpeed::Fast => Some(20.0), // Tetris Guideline
+            SoftDropSpeed::Instant => None,
+            SoftDropSpeed::Repeat => Some(1.0), // gravity is left alone while it repeats
+        }
+    }
+
+    /// returns the next speed, wrapping around to the first
+    pub fn next(&self) -> SoftDropSpeed {
+        let speeds = SoftDropSpeed::iter().collect::<Vec<_>>();
+        let i = speeds.iter().position(|s| s == self).unwrap_or(0);
+        speeds[(i + 1) % speeds.len()]
+    }
+}
+
+/// which set of default key bindings is used
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter)]
+pub enum KeyLayout {
+    Standard,
+    LeftHanded, // the rotate, hold and 180 keys move to the right side of the keyboard
+}
+
+impl KeyLayout {
+    pub fn name(&self) -> &'static str {
+        match self {
+            KeyLayout::Standard => "Standard",
+            KeyLayout::LeftHanded => "Left Handed",
+        }
+    }
+
+    /// returns the next layout, wrapping around to the first
+    pub fn next(&self) -> KeyLayout {
+        let layouts = KeyLayout::iter().collect::<Vec<_>>();
+        let i = layouts.iter().position(|l| l == self).unwrap_or(0);
+        layouts[(i + 1) % layouts.len()]
+    }
+}
+
+/// cycles the trainer through off and then each block type
+pub fn next_trainer_block(current: Option<RustominoType>) -> Option<RustominoType> {
+    match current {
+        None => RustominoType::iter().next(),
+        Some(rtype) => RustominoType::iter().skip_while(|r| *r != rtype).nth(1),
+    }
+}
@@ -0,0 +1,124 @@
+use ggez::graphics::Color;
+use strum::{EnumIter, IntoEnumIterator};
+
+/// how the playing area is drawn
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    pub block_padding: f32,      // space between blocks
+    pub block_border_width: f32, // outline drawn around each block, 0 for none
+    pub block_border_color: Color,
+    pub locked_block_shade: f32, // how much darker locked blocks are than the active block
+    pub background_color: Color,
+    pub staging_background_color: Color,
+    pub playfield_background_color: Color,
+    pub preview_background_color: Color,
+    pub hold_background_color: Color,
+    pub ghost_color: Color,
+    pub ghost_line_width: f32, // as a fraction of the block size
+    pub frame_color: Color,    // frame drawn around the playfield and staging area
+    pub frame_width: f32,
+    pub active_shadow_offset: f32, // drop shadow under the active block, 0 for none
+    pub active_shadow_color: Color,
+}
+
+const CLASSIC_THEME: Theme = Theme {
+    block_padding: 1.,
+    block_border_width: 0.,
+    block_border_color: Color::new(0.0, 0.0, 0.0, 0.0),
+    locked_block_shade: 0.15,
+    background_color: Color::new(0.0, 0.29, 0.38, 1.0),
+    staging_background_color: Color::new(0.0, 0.0, 0.0, 0.5),
+    playfield_background_color: Color::new(0.0, 0.0, 0.0, 0.5),
+    preview_background_color: Color::new(0.0, 0.0, 0.0, 0.5),
+    hold_background_color: Color::new(0.0, 0.0, 0.0, 0.2),
+    ghost_color: Color::new(0.7, 0.7, 0.7, 1.0),
+    ghost_line_width: 0.1,
+    frame_color: Color::new(0.0, 0.18, 0.24, 1.0),
+    frame_width: 4.,
+    active_shadow_offset: 3.,
+    active_shadow_color: Color::new(0.0, 0.0, 0.0, 0.45),
+};
+
+const NIGHT_THEME: Theme = Theme {
+    block_padding: 3.,
+    block_border_width: 2.,
+    block_border_color: Color::new(0.0, 0.0, 0.0, 0.4),
+    locked_block_shade: 0.25,
+    background_color: Color::new(0.05, 0.05, 0.1, 1.0),
+    staging_background_color: Color::new(0.0, 0.0, 0.0, 0.6),
+    playfield_background_color: Color::new(0.0, 0.0, 0.0, 0.6),
+    preview_background_color: Color::new(0.0, 0.0, 0.0, 0.6),
+    hold_background_color: Color::new(0.0, 0.0, 0.0, 0.4),
+    ghost_color: Color::new(0.5, 0.6, 0.9, 1.0),
+    ghost_line_width: 0.05,
+    frame_color: Color::new(0.2, 0.25, 0.45, 1.0),
+    frame_width: 3.,
+    active_shadow_offset: 2.,
+    active_shadow_color: Color::new(0.0, 0.0, 0.0, 0.6),
+};
+
+/// the built in themes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter)]
+pub enum ThemeName {
+    Classic,
+    Night,
+}
+
+impl ThemeName {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ThemeName::Classic => "Classic",
+            ThemeName::Night => "Night",
+        }
+    }
+
+    pub fn theme(&self) -> Theme {
+        match self {
+            ThemeName::Classic => CLASSIC_THEME,
+            ThemeName::Night => NIGHT_THEME,
+        }
+    }
+
+    /// returns the next theme, wrapping around to the first
+    pub fn next(&self) -> ThemeName {
+        let themes = ThemeName::iter().collect::<Vec<_>>();
+        let i = themes.iter().position(|t| t == self).unwrap_or(0);
+        themes[(i + 1) % themes.len()]
+    }
+}
+
+/// colors the background can be set to instead of the theme's own
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter)]
+pub enum BackgroundColor {
+    Theme, // use the current theme's background
+    Black,
+    Slate,
+    Plum,
+}
+
+impl BackgroundColor {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BackgroundColor::Theme => "Theme",
+            BackgroundColor::Black => "Black",
+            BackgroundColor::Slate => "Slate",
+            BackgroundColor::Plum => "Plum",
+        }
+    }
+
+    pub fn color(&self, theme: &Theme) -> Color {
+        match self {
+            BackgroundColor::Theme => theme.background_color,
+            BackgroundColor::Black => Color::new(0.0, 0.0, 0.0, 1.0),
+            BackgroundColor::Slate => Color::new(0.18, 0.21, 0.25, 1.0),
+            BackgroundColor::Plum => Color::new(0.24, 0.1, 0.22, 1.0),
+        }
+    }
+
+    /// returns the next background color, wrapping around to the first
+    pub fn next(&self) -> BackgroundColor {
+        let colors = BackgroundColor::iter().collect::<Vec<_>>();
+        let i = colors.iter().position(|c| c == self).unwrap_or(0);
+        colors[(i + 1) % colors.len()]
+    }
+}
@@ -17,3 +17,23 @@ pub fn rgb_to_grayscale(rgb: Color) -> Color {
     let gray = 0.2989 * rgb.r + 0.5870 * rgb.g + 0.1140 * rgb.b;
     Color::new(gray, gray, gray, rgb.a)
 }
+
+// blend a color towards white by the given amount (0.0 - 1.0)
+pub fn brighten(rgb: Color, amount: f32) -> Color {
+    Color::new(
+        rgb.r + (1.0 - rgb.r) * amount,
+        rgb.g + (1.0 - rgb.g) * amount,
+        rgb.b + (1.0 - rgb.b) * amount,
+        rgb.a,
+    )
+}
+
+// blend a color towards black by the given amount (0.0 - 1.0)
+pub fn darken(rgb: Color, amount: f32) -> Color {
+    Color::new(
+        rgb.r * (1.0 - amount),
+        rgb.g * (1.0 - amount),
+        rgb.b * (1.0 - amount),
+        rgb.a,
+    )
+}